@@ -0,0 +1,72 @@
+use axum::{Router, debug_handler, extract::FromRequestParts, http::request::Parts, routing};
+use sea_orm::EntityTrait;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::app::{ApiError, AppResponse, AppResult, AppState, ValidJson, auth::Principal, logger};
+use crate::entity::prelude::*;
+
+pub fn create_router() -> Router<AppState> {
+    Router::new().route("/log-level", routing::get(get_log_level).put(set_log_level))
+}
+
+/// 管理员，账号需要在 `auth.admin_accounts` 中
+struct Admin {
+    account: String,
+}
+
+impl FromRequestParts<AppState> for Admin {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let principal = parts
+            .extensions
+            .get::<Principal>()
+            .ok_or_else(|| ApiError::Unauthenticated(String::new()))?;
+        // 用户名可以重复，按唯一的账号判断
+        let user = SysUser::find_by_id(&principal.id)
+            .one(state.db.write())
+            .await?
+            .ok_or_else(|| ApiError::Unauthenticated(String::from("user not found")))?;
+        if !crate::config::current().auth().is_admin(&user.account) {
+            tracing::warn!("User {} is not an admin", user.account);
+            return Err(ApiError::Forbidden(String::from("admin only")));
+        }
+        Ok(Admin {
+            account: user.account,
+        })
+    }
+}
+
+#[debug_handler(state = AppState)]
+async fn get_log_level(_admin: Admin) -> AppResult<LogLevelVO> {
+    Ok(AppResponse::ok(Some(LogLevelVO {
+        filter: logger::current_filter(),
+    })))
+}
+
+/// 修改后立即生效，配置文件热加载时会被 logging 配置覆盖
+#[debug_handler(state = AppState)]
+async fn set_log_level(
+    admin: Admin,
+    ValidJson(dto): ValidJson<LogLevelDTO>,
+) -> AppResult<LogLevelVO> {
+    logger::set_filter(&dto.filter).map_err(|err| ApiError::ValidationError(err.to_string()))?;
+    tracing::info!("Log level changed by {}", admin.account);
+    Ok(AppResponse::ok(Some(LogLevelVO { filter: dto.filter })))
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct LogLevelDTO {
+    /// EnvFilter 语法，例如 `debug,sqlx=warn`
+    #[validate(length(min = 1, message = "filter不能为空"))]
+    pub filter: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LogLevelVO {
+    pub filter: String,
+}
@@ -1,22 +1,15 @@
-use std::net::SocketAddr;
-
 use crate::app::auth::{Principal, get_jwt};
+use crate::app::metrics;
 use crate::app::{
-    ApiError, AppResponse, AppResult, AppState, Gender, ResponseErrorCode, ValidJson,
+    ApiError, AppResponse, AppResult, AppState, ClientIp, ResponseErrorCode, ValidJson,
     get_auth_layer,
 };
 use crate::entity::{prelude::*, sys_user};
 use crate::utils::crypt;
-use axum::extract::ConnectInfo;
-use axum::response::Response;
-use axum::{Extension, Json};
+use axum::Extension;
 use axum::{Router, debug_handler, extract::State, routing};
-use sea_orm::sea_query::ExprTrait;
-use sea_orm::{ActiveValue, ColumnTrait, Condition, QueryFilter};
-use sea_orm::{
-    DeriveIntoActiveModel, EntityTrait,
-    prelude::{Date, DateTime},
-};
+use sea_orm::EntityTrait;
+use sea_orm::{ColumnTrait, QueryFilter};
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
@@ -28,22 +21,26 @@ pub fn create_router() -> Router<AppState> {
 }
 
 #[debug_handler]
-#[tracing::instrument(name = "login", skip_all, fields(username = %dto.username,ip=%addr.ip()))]
+#[tracing::instrument(name = "login", skip_all, fields(username = %dto.username,ip=%client_ip))]
 async fn login(
-    State(AppState { db }): State<AppState>,
-    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    State(AppState { db, .. }): State<AppState>,
+    client_ip: ClientIp,
     ValidJson(dto): ValidJson<UserLoginDTO>,
 ) -> AppResult<LoginVO> {
     tracing::info!("login username:{}", &dto.username);
     let user = SysUser::find()
         .filter(sys_user::Column::Account.eq(&dto.username))
-        .one(&db)
+        .one(db.write())
         .await?
-        .ok_or_else(|| ApiError::Biz((ResponseErrorCode::UserNameOrPasswordError)))?;
+        .ok_or_else(|| {
+            metrics::record_login(false);
+            ApiError::Biz(ResponseErrorCode::UserNameOrPasswordError)
+        })?;
 
     let user_password_hash = &user.password;
     let input_password = &dto.password;
     let password_match = crypt::verify_password(input_password, user_password_hash)?;
+    metrics::record_login(password_match);
     if !password_match {
         return Err(ApiError::Biz(ResponseErrorCode::UserNameOrPasswordError));
     }
@@ -54,7 +51,7 @@ async fn login(
         permissions: vec![],
     };
     let access_token = get_jwt().encode(principal)?;
-    tracing::info!(" login suceessaccess_token:{}", &access_token);
+    tracing::info!("login success user_id:{}", &user.id);
     Ok(AppResponse::ok(Some(LoginVO { access_token })))
 }
 
@@ -1,3 +1,4 @@
+mod admin;
 mod auth;
 mod user;
 use axum::Router;
@@ -12,7 +13,12 @@ use crate::app::{ApiResult, get_auth_layer};
 /// The returned router is ready to be used by the axum server.
 pub fn create_router() -> Router<AppState> {
     Router::new()
-        .nest("/api", Router::new().nest("/users", user::create_router()))
+        .nest(
+            "/api",
+            Router::new()
+                .nest("/users", user::create_router())
+                .nest("/admin", admin::create_router()),
+        )
         .route_layer(get_auth_layer())
         .nest("/auth", auth::create_router())
         .fallback(handler_not_found)
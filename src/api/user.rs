@@ -1,35 +1,62 @@
 use crate::{
     app::{
-        ApiError, ApiResult, AppResponse, AppResult, AppState, BasePageDTO, Gender, PageInfoData,
-        Path, ResponseErrorCode, ValidJson, ValidQuery,
+        ApiError, ApiResult, AppResponse, AppResult, AppState, BasePageDTO, Gender, Multipart,
+        PageInfoData, Path, ResponseErrorCode, TransactionLayer, Tx, ValidJson, ValidQuery, WithTx,
     },
     entity::{prelude::SysUser, sys_user},
     utils::{
-        self,
-        crypt::{self, encode_password},
+        crypt::encode_password,
+        sheet::{self, SheetFormat},
     },
 };
+use std::collections::{HashMap, HashSet};
+
 use anyhow::Context;
 use axum::{
-    Router, debug_handler,
+    Router,
+    body::{Body, Bytes},
+    debug_handler,
     extract::State,
+    http::header,
+    response::{IntoResponse, Response},
     routing::{delete, get, post, put},
 };
+use csv::StringRecord;
+use futures::{StreamExt, TryStreamExt, stream};
+use sea_orm::prelude::*;
 use sea_orm::{
-    ActiveValue, ColumnTrait, Condition, EntityTrait, IntoActiveModel, PaginatorTrait, QueryFilter,
-    QueryOrder, QueryTrait, prelude::Date,
+    ActiveValue, ColumnTrait, Condition, EntityTrait, IntoActiveModel, Iterable, PaginatorTrait,
+    QueryFilter, QueryOrder, QuerySelect, QueryTrait, Select, TransactionTrait, prelude::Date,
 };
-use sea_orm::{IntoActiveModel as _, prelude::*};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use sys_user::ActiveModel;
 use validator::Validate;
+
+const IMPORT_FILE_FIELD: &str = "file";
+const EXPORT_BATCH_SIZE: u64 = 500;
+const EXPORT_HEADERS: [&str; 8] = [
+    "id",
+    "username",
+    "gender",
+    "account",
+    "mobile_phone",
+    "birthday",
+    "enbaled",
+    "created_date",
+];
+
 pub fn create_router() -> Router<AppState> {
     Router::new()
         .route("/pagination", post(find_page))
+        .route("/import", post(import_users))
+        .route("/export", get(export_users))
+        .route("/batch/delete", post(batch_delete_users))
+        .route("/batch/enable", post(batch_enable_users))
+        .route("/batch/disable", post(batch_disable_users))
         .route("/", get(get_users))
-        .route("/", post(add_user))
-        .route("/", put(update_user))
-        .route("/{id}", delete(delete_user))
+        .route("/", post(add_user).layer(TransactionLayer::new()))
+        .route("/", put(update_user).layer(TransactionLayer::new()))
+        .route("/{id}", delete(delete_user).layer(TransactionLayer::new()))
 }
 
 #[derive(Debug, Deserialize, Validate)]
@@ -42,6 +69,69 @@ pub struct UserQueryDTO {
     pagination: BasePageDTO,
 }
 
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct UserImportDTO {
+    /// 只校验不入库
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct UserExportDTO {
+    keyword: Option<String>,
+
+    #[serde(default)]
+    format: SheetFormat,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserImportVO {
+    pub dry_run: bool,
+    pub total: usize,
+    pub imported: usize,
+    pub errors: Vec<ImportRowError>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportRowError {
+    /// 表格中的行号（表头为第 1 行）
+    pub row: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UserBatchDTO {
+    #[validate(length(min = 1, message = "ids不能为空"))]
+    pub ids: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserBatchVO {
+    pub succeeded: usize,
+    pub results: Vec<BatchItemResult>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchItemResult {
+    pub id: String,
+    pub outcome: BatchOutcome,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchOutcome {
+    Success,
+    NotFound,
+    AlreadyInState,
+}
+
 #[derive(Debug, Deserialize, Validate)]
 pub struct UserUpdateDTO {
     // not emepty
@@ -74,11 +164,9 @@ pub struct UserAddDTO {
     pub enbaled: bool,
 }
 
-#[debug_handler]
-async fn add_user(
-    State(AppState { db }): State<AppState>,
-    ValidJson(dto): ValidJson<UserAddDTO>,
-) -> AppResult<()> {
+#[debug_handler(state = AppState)]
+async fn add_user(WithTx(ValidJson(dto), tx): WithTx<ValidJson<UserAddDTO>>) -> AppResult<()> {
+    check_user_unique(&*tx, &dto.account, &dto.mobile_phone, None).await?;
     let mut active_model = dto.into_active_model();
     active_model.password = sea_orm::ActiveValue::Set(encode_password(
         &active_model
@@ -86,48 +174,87 @@ async fn add_user(
             .take()
             .ok_or_else(|| ApiError::Biz(ResponseErrorCode::DbPwdNotFind))?,
     )?);
-    let _am = active_model.insert(&db).await?;
+    let _am = active_model.insert(&*tx).await?;
     Ok(AppResponse::ok_whitok_no_data())
 }
-#[debug_handler]
+#[debug_handler(state = AppState)]
 async fn update_user(
-    State(AppState { db }): State<AppState>,
-    ValidJson(dto): ValidJson<UserUpdateDTO>,
+    WithTx(ValidJson(dto), tx): WithTx<ValidJson<UserUpdateDTO>>,
 ) -> AppResult<()> {
     let existed_user = SysUser::find_by_id(&dto.id)
-        .one(&db)
+        .one(&*tx)
         .await?
         .ok_or_else(|| ApiError::Biz(ResponseErrorCode::FindNotUser))?;
+    check_user_unique(
+        &*tx,
+        &dto.user.account,
+        &dto.user.mobile_phone,
+        Some(&dto.id),
+    )
+    .await?;
     let old_password = existed_user.password.clone();
-    let password = dto.user.password.clone();
     let mut existed_user_model = existed_user.into_active_model();
-
+    // DTO 中没有主键，只把 DTO 设置的字段应用到查出的用户上
     let mut active_model = dto.user.into_active_model();
-    existed_user_model.clone_from(&active_model);
-    // existed_user_model.id = ActiveValue::Unchanged(id);
-    if password.is_empty() {
-        active_model.password = ActiveValue::Unchanged(old_password);
-    } else {
-        let password_value = &active_model
-            .password
-            .take()
-            .ok_or_else(|| ApiError::Biz(ResponseErrorCode::DbPwdNotFind))?;
-        existed_user_model.password = ActiveValue::Set(encode_password(password_value)?);
+    for column in sys_user::Column::iter() {
+        if let ActiveValue::Set(value) = active_model.take(column) {
+            existed_user_model.set(column, value);
+        }
     }
-    let _ret = active_model.update(&db).await?;
+    existed_user_model.password = match existed_user_model.password.take() {
+        Some(password) if !password.is_empty() => ActiveValue::Set(encode_password(&password)?),
+        _ => ActiveValue::Unchanged(old_password),
+    };
+    let _ret = existed_user_model.update(&*tx).await?;
     Ok(AppResponse::ok_whitok_no_data())
 }
 
-#[debug_handler]
-async fn delete_user(
-    State(AppState { db }): State<AppState>,
-    Path(id): Path<String>,
-) -> AppResult<()> {
+/// 校验账号、手机号唯一（更新时排除自身）
+async fn check_user_unique<C: ConnectionTrait>(
+    db: &C,
+    account: &str,
+    mobile_phone: &str,
+    exclude_id: Option<&str>,
+) -> ApiResult<()> {
+    let existed_users = SysUser::find()
+        .select_only()
+        .columns([sys_user::Column::Account, sys_user::Column::MobilePhone])
+        .filter(
+            Condition::any()
+                .add(sys_user::Column::Account.eq(account))
+                .add(sys_user::Column::MobilePhone.eq(mobile_phone)),
+        )
+        .apply_if(exclude_id, |query, id| {
+            query.filter(sys_user::Column::Id.ne(id))
+        })
+        .into_tuple::<(String, String)>()
+        .all(db)
+        .await?;
+    if existed_users.iter().any(|(existed, _)| existed == account) {
+        return Err(ApiError::Conflict {
+            code: ResponseErrorCode::AccountAlreadyExists,
+            field: Some("account"),
+        });
+    }
+    if existed_users
+        .iter()
+        .any(|(_, existed)| existed == mobile_phone)
+    {
+        return Err(ApiError::Conflict {
+            code: ResponseErrorCode::MobilePhoneAlreadyExists,
+            field: Some("mobile_phone"),
+        });
+    }
+    Ok(())
+}
+
+#[debug_handler(state = AppState)]
+async fn delete_user(Path(id): Path<String>, tx: Tx) -> AppResult<()> {
     let existed_user = SysUser::find_by_id(&id)
-        .one(&db)
+        .one(&*tx)
         .await?
         .ok_or_else(|| ApiError::Biz(ResponseErrorCode::FindNotUser))?;
-    let result = existed_user.delete(&db).await?;
+    let result = existed_user.delete(&*tx).await?;
     tracing::info!(
         "delete user: {},affected rows: {}",
         id,
@@ -135,16 +262,150 @@ async fn delete_user(
     );
     Ok(AppResponse::ok_whitok_no_data())
 }
+#[debug_handler]
+async fn batch_delete_users(
+    State(AppState { db, .. }): State<AppState>,
+    ValidJson(dto): ValidJson<UserBatchDTO>,
+) -> AppResult<UserBatchVO> {
+    let ids = check_batch_ids(dto.ids)?;
+    let txn = db.write().begin().await?;
+    let existed_ids = SysUser::find()
+        .select_only()
+        .column(sys_user::Column::Id)
+        .filter(sys_user::Column::Id.is_in(&ids))
+        .into_tuple::<String>()
+        .all(&txn)
+        .await?
+        .into_iter()
+        .collect::<HashSet<_>>();
+    let result = SysUser::delete_many()
+        .filter(sys_user::Column::Id.is_in(&existed_ids))
+        .exec(&txn)
+        .await?;
+    txn.commit().await?;
+    tracing::info!(
+        "batch delete users: {:?},affected rows: {}",
+        existed_ids,
+        result.rows_affected
+    );
+    let results = ids
+        .into_iter()
+        .map(|id| {
+            let outcome = if existed_ids.contains(&id) {
+                BatchOutcome::Success
+            } else {
+                BatchOutcome::NotFound
+            };
+            BatchItemResult { id, outcome }
+        })
+        .collect();
+    Ok(AppResponse::ok(Some(UserBatchVO::new(results))))
+}
+
+#[debug_handler]
+async fn batch_enable_users(
+    State(AppState { db, .. }): State<AppState>,
+    ValidJson(dto): ValidJson<UserBatchDTO>,
+) -> AppResult<UserBatchVO> {
+    batch_set_enabled(db.write(), dto.ids, true).await
+}
+
+#[debug_handler]
+async fn batch_disable_users(
+    State(AppState { db, .. }): State<AppState>,
+    ValidJson(dto): ValidJson<UserBatchDTO>,
+) -> AppResult<UserBatchVO> {
+    batch_set_enabled(db.write(), dto.ids, false).await
+}
+
+async fn batch_set_enabled(
+    db: &DatabaseConnection,
+    ids: Vec<String>,
+    enabled: bool,
+) -> AppResult<UserBatchVO> {
+    let ids = check_batch_ids(ids)?;
+    let txn = db.begin().await?;
+    let existed_users = SysUser::find()
+        .select_only()
+        .columns([sys_user::Column::Id, sys_user::Column::Enbaled])
+        .filter(sys_user::Column::Id.is_in(&ids))
+        .into_tuple::<(String, bool)>()
+        .all(&txn)
+        .await?
+        .into_iter()
+        .collect::<HashMap<_, _>>();
+    let changed_ids = existed_users
+        .iter()
+        .filter(|(_, user_enabled)| **user_enabled != enabled)
+        .map(|(id, _)| id)
+        .collect::<Vec<_>>();
+    let result = SysUser::update_many()
+        .col_expr(sys_user::Column::Enbaled, Expr::value(enabled))
+        .filter(sys_user::Column::Id.is_in(changed_ids))
+        .exec(&txn)
+        .await?;
+    txn.commit().await?;
+    tracing::info!(
+        "batch set users enabled: {},affected rows: {}",
+        enabled,
+        result.rows_affected
+    );
+    let results = ids
+        .into_iter()
+        .map(|id| {
+            let outcome = match existed_users.get(&id) {
+                None => BatchOutcome::NotFound,
+                Some(user_enabled) if *user_enabled == enabled => BatchOutcome::AlreadyInState,
+                Some(_) => BatchOutcome::Success,
+            };
+            BatchItemResult { id, outcome }
+        })
+        .collect();
+    Ok(AppResponse::ok(Some(UserBatchVO::new(results))))
+}
+
+/// 去重（保持顺序）并校验批量数量上限
+fn check_batch_ids(ids: Vec<String>) -> ApiResult<Vec<String>> {
+    let mut seen = HashSet::with_capacity(ids.len());
+    let ids = ids
+        .into_iter()
+        .filter(|id| seen.insert(id.clone()))
+        .collect::<Vec<_>>();
+    if ids.len() > crate::config::current().user().batch_max_size() {
+        return Err(ApiError::Biz(ResponseErrorCode::BatchSizeExceeded));
+    }
+    Ok(ids)
+}
+
+impl UserBatchVO {
+    fn new(results: Vec<BatchItemResult>) -> Self {
+        let succeeded = results
+            .iter()
+            .filter(|result| result.outcome == BatchOutcome::Success)
+            .count();
+        Self { succeeded, results }
+    }
+}
+
 #[debug_handler]
 async fn find_page(
-    State(AppState { db }): State<AppState>,
+    State(AppState { db, .. }): State<AppState>,
     ValidQuery(UserQueryDTO {
         keyword,
         pagination,
     }): ValidQuery<UserQueryDTO>,
 ) -> AppResult<PageInfoData<sys_user::Model>> {
-    let paginate = SysUser::find()
-        .apply_if(keyword.as_ref(), |query, keyword| {
+    let paginate = find_users_by_keyword(keyword.as_ref()).paginate(db.read(), pagination.size);
+    let total = paginate.num_items().await?;
+    let users = paginate.fetch_page(pagination.page - 1).await?;
+    let pigination = PageInfoData::from_pagination(pagination, total, users);
+    //.context("can not find users")?;
+    Ok(AppResponse::ok(Some(pigination)))
+}
+
+fn find_users_by_keyword(keyword: Option<&String>) -> Select<SysUser> {
+    SysUser::find()
+        .apply_if(keyword, |query, keyword| {
             query.filter(
                 Condition::any()
                     .add(sys_user::Column::Username.contains(keyword))
@@ -152,18 +413,290 @@ async fn find_page(
             )
         })
         .order_by_desc(sys_user::Column::CreatedDate)
-        .paginate(&db, pagination.size);
-    let total = paginate.num_items().await?;
-    let users = paginate.fetch_page(pagination.page - 1).await?;
-    let pigination = PageInfoData::from_pagination(pagination, total, users);
-    //.context("can not find users")?;
-    Ok(AppResponse::ok(Some(pigination)))
+        .order_by_desc(sys_user::Column::Id)
+}
+
+/// 排在游标 (created_date, id) 之后的用户，created_date 相同的数据按 id 区分，分批时不会重复或遗漏
+fn find_users_after(
+    keyword: Option<&String>,
+    cursor: Option<(DateTime, String)>,
+) -> Select<SysUser> {
+    find_users_by_keyword(keyword).apply_if(cursor, |query, (created_date, id)| {
+        query.filter(
+            Condition::any()
+                .add(sys_user::Column::CreatedDate.lt(created_date))
+                .add(
+                    Condition::all()
+                        .add(sys_user::Column::CreatedDate.eq(created_date))
+                        .add(sys_user::Column::Id.lt(id)),
+                ),
+        )
+    })
+}
+
+#[debug_handler]
+async fn import_users(
+    State(AppState { db, .. }): State<AppState>,
+    ValidQuery(UserImportDTO { dry_run }): ValidQuery<UserImportDTO>,
+    Multipart(mut multipart): Multipart,
+) -> AppResult<UserImportVO> {
+    let (format, bytes) = read_import_file(&mut multipart).await?;
+    let (headers, records) = sheet::read_records(format, &bytes)
+        .map_err(|err| ApiError::ValidationError(format!("invalid import file: {err:#}")))?;
+
+    let mut errors = Vec::new();
+    let mut users = Vec::with_capacity(records.len());
+    for (index, record) in records.iter().enumerate() {
+        // 表头占第 1 行
+        let row = index + 2;
+        match parse_import_row(row, &headers, record) {
+            Ok(dto) => users.push((row, dto)),
+            Err(row_errors) => errors.extend(row_errors),
+        }
+    }
+    errors.extend(find_import_conflicts(db.write(), &users).await?);
+    let total = records.len();
+    // 试运行只做校验和重复检查，不写入数据库
+    if dry_run || !errors.is_empty() {
+        return Ok(AppResponse::ok(Some(UserImportVO {
+            dry_run,
+            total,
+            imported: 0,
+            errors,
+        })));
+    }
+
+    // bcrypt 计算较慢，在阻塞线程池中完成，避免占用异步工作线程
+    let users = tokio::task::spawn_blocking(move || {
+        users
+            .into_iter()
+            .map(|(row, dto)| {
+                let mut active_model = dto.into_active_model();
+                active_model.password = ActiveValue::Set(encode_password(
+                    active_model
+                        .password
+                        .take()
+                        .ok_or_else(|| ApiError::Biz(ResponseErrorCode::DbPwdNotFind))?,
+                )?);
+                Ok((row, active_model))
+            })
+            .collect::<ApiResult<Vec<_>>>()
+    })
+    .await
+    .map_err(anyhow::Error::from)??;
+
+    // 全部成功才提交，任意一行失败整体回滚
+    let txn = db.write().begin().await?;
+    for (row, active_model) in users {
+        if let Err(err) = active_model.insert(&txn).await {
+            let (field, message) = match ApiError::from(err) {
+                ApiError::Conflict { code, field } => (field, code.message().to_string()),
+                err => (None, err.to_string()),
+            };
+            errors.push(ImportRowError {
+                row,
+                field: field.map(String::from),
+                message,
+            });
+            break;
+        }
+    }
+    let imported = if !errors.is_empty() {
+        txn.rollback().await?;
+        0
+    } else {
+        txn.commit().await?;
+        total
+    };
+    tracing::info!(
+        "import users: total: {}, imported: {}, dry run: {}",
+        total,
+        imported,
+        dry_run
+    );
+    Ok(AppResponse::ok(Some(UserImportVO {
+        dry_run,
+        total,
+        imported,
+        errors,
+    })))
+}
+
+async fn read_import_file(
+    multipart: &mut axum::extract::Multipart,
+) -> ApiResult<(SheetFormat, Bytes)> {
+    while let Some(field) = multipart.next_field().await? {
+        if field.name() != Some(IMPORT_FILE_FIELD) {
+            continue;
+        }
+        let format = field
+            .file_name()
+            .and_then(SheetFormat::from_file_name)
+            .ok_or_else(|| ApiError::Biz(ResponseErrorCode::ImportFileFormatUnsupported))?;
+        return Ok((format, field.bytes().await?));
+    }
+    Err(ApiError::Biz(ResponseErrorCode::ImportFileNotFound))
+}
+
+/// 检查导入数据与库中数据、以及文件内部的账号和手机号重复
+async fn find_import_conflicts(
+    db: &DatabaseConnection,
+    users: &[(usize, UserAddDTO)],
+) -> ApiResult<Vec<ImportRowError>> {
+    let existed_users = SysUser::find()
+        .select_only()
+        .columns([sys_user::Column::Account, sys_user::Column::MobilePhone])
+        .filter(
+            Condition::any()
+                .add(sys_user::Column::Account.is_in(users.iter().map(|(_, u)| &u.account)))
+                .add(
+                    sys_user::Column::MobilePhone.is_in(users.iter().map(|(_, u)| &u.mobile_phone)),
+                ),
+        )
+        .into_tuple::<(String, String)>()
+        .all(db)
+        .await?;
+    let (mut accounts, mut mobile_phones): (HashSet<_>, HashSet<_>) = existed_users
+        .iter()
+        .map(|(account, mobile_phone)| (account.as_str(), mobile_phone.as_str()))
+        .unzip();
+    let mut errors = Vec::new();
+    for (row, user) in users {
+        if !accounts.insert(&user.account) {
+            errors.push(ImportRowError {
+                row: *row,
+                field: Some(String::from("account")),
+                message: ResponseErrorCode::AccountAlreadyExists
+                    .message()
+                    .to_string(),
+            });
+        }
+        if !mobile_phones.insert(&user.mobile_phone) {
+            errors.push(ImportRowError {
+                row: *row,
+                field: Some(String::from("mobile_phone")),
+                message: ResponseErrorCode::MobilePhoneAlreadyExists
+                    .message()
+                    .to_string(),
+            });
+        }
+    }
+    Ok(errors)
+}
+
+fn parse_import_row(
+    row: usize,
+    headers: &StringRecord,
+    record: &StringRecord,
+) -> Result<UserAddDTO, Vec<ImportRowError>> {
+    let dto = record
+        .deserialize::<UserAddDTO>(Some(headers))
+        .map_err(|err| {
+            let field = match err.kind() {
+                csv::ErrorKind::Deserialize { err, .. } => err
+                    .field()
+                    .and_then(|index| headers.get(index as usize))
+                    .map(String::from),
+                _ => None,
+            };
+            vec![ImportRowError {
+                row,
+                field,
+                message: err.to_string(),
+            }]
+        })?;
+    dto.validate().map_err(|errs| {
+        errs.field_errors()
+            .into_iter()
+            .flat_map(|(field, field_errors)| {
+                field_errors.iter().map(move |error| ImportRowError {
+                    row,
+                    field: Some(field.to_string()),
+                    message: error
+                        .message
+                        .as_ref()
+                        .map(|message| message.to_string())
+                        .unwrap_or_else(|| error.code.to_string()),
+                })
+            })
+            .collect::<Vec<_>>()
+    })?;
+    Ok(dto)
+}
+
+#[debug_handler]
+async fn export_users(
+    State(AppState { db, .. }): State<AppState>,
+    ValidQuery(UserExportDTO { keyword, format }): ValidQuery<UserExportDTO>,
+) -> ApiResult<Response> {
+    let body = match format {
+        SheetFormat::Csv => {
+            let header = sheet::write_csv([EXPORT_HEADERS])?;
+            // 按 (created_date, id) 游标分批查询，边查边输出
+            let rows = stream::try_unfold(Some(None), move |cursor| {
+                let db = db.clone();
+                let keyword = keyword.clone();
+                async move {
+                    let Some(cursor) = cursor else {
+                        return Ok::<_, anyhow::Error>(None);
+                    };
+                    let users = find_users_after(keyword.as_ref(), cursor)
+                        .limit(EXPORT_BATCH_SIZE)
+                        .all(db.read())
+                        .await?;
+                    let next_cursor = (users.len() as u64 == EXPORT_BATCH_SIZE).then(|| {
+                        users
+                            .last()
+                            .map(|user| (user.created_date, user.id.clone()))
+                    });
+                    let chunk = sheet::write_csv(users.iter().map(export_row))?;
+                    Ok(Some((chunk, next_cursor)))
+                }
+            });
+            Body::from_stream(stream::once(async { Ok(header) }).chain(rows.into_stream()))
+        }
+        SheetFormat::Xlsx => {
+            // xlsx 需要在内存中生成完整文件，限制行数
+            let max_rows = crate::config::current().user().xlsx_export_max_rows();
+            let users = find_users_by_keyword(keyword.as_ref())
+                .limit(max_rows + 1)
+                .all(db.read())
+                .await?;
+            if users.len() as u64 > max_rows {
+                return Err(ApiError::Biz(ResponseErrorCode::ExportSizeExceeded));
+            }
+            let rows = users.iter().map(export_row).collect::<Vec<_>>();
+            Body::from(sheet::write_xlsx(&EXPORT_HEADERS, &rows)?)
+        }
+    };
+    let disposition = format!("attachment; filename=\"users.{}\"", format.extension());
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+fn export_row(user: &sys_user::Model) -> Vec<String> {
+    vec![
+        user.id.clone(),
+        user.username.clone(),
+        user.gender.to_value(),
+        user.account.clone(),
+        user.mobile_phone.clone(),
+        user.birthday.to_string(),
+        user.enbaled.to_string(),
+        user.created_date.to_string(),
+    ]
 }
 
 #[debug_handler]
 // #[tracing::instrument(name = "get_users", fields(pay_method = "alipay"), skip(db))]
 async fn get_users(
-    State(AppState { db }): State<AppState>,
+    State(AppState { db, .. }): State<AppState>,
 ) -> ApiResult<AppResponse<Vec<sys_user::Model>>> {
     let users = SysUser::find()
         .filter(
@@ -175,7 +708,7 @@ async fn get_users(
                         .add(Condition::any().add(sys_user::Column::Enbaled.eq(true))),
                 ),
         )
-        .all(&db)
+        .all(db.read())
         .await
         .context("can not find users")?;
     Ok(AppResponse::ok(Some(users)))
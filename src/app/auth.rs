@@ -1,9 +1,20 @@
-use std::{borrow::Cow, str, sync::LazyLock, time::Duration};
+use std::{
+    borrow::Cow,
+    str,
+    sync::{
+        LazyLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
 
 use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, get_current_timestamp};
 use serde::{Deserialize, Serialize};
 
-static DEFAULT_JWT: LazyLock<JWT> = LazyLock::new(JWT::default);
+use crate::config::auth::AuthConfig;
+
+static DEFAULT_JWT: LazyLock<JWT> =
+    LazyLock::new(|| JWT::new(JwtConfig::from(crate::config::get().auth())));
 
 const DEFAULT_SECRET: &str = "1234567890";
 const DEFAULT_AUDIENCE: &str = "aduience";
@@ -45,13 +56,39 @@ impl Default for JwtConfig {
     }
 }
 
+impl From<&'static AuthConfig> for JwtConfig {
+    fn from(config: &'static AuthConfig) -> Self {
+        let default = JwtConfig::default();
+        if config.jwt_secret().is_none() {
+            tracing::warn!("auth.jwt_secret is not configured, using the default secret");
+        }
+        Self {
+            secret: config
+                .jwt_secret()
+                .map(Cow::Borrowed)
+                .unwrap_or(default.secret),
+            exp: config.jwt_expiration().unwrap_or(default.exp),
+            aduience: config
+                .jwt_audience()
+                .map(Cow::Borrowed)
+                .unwrap_or(default.aduience),
+            issuer: config
+                .jwt_issuer()
+                .map(Cow::Borrowed)
+                .unwrap_or(default.issuer),
+        }
+    }
+}
+
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug)]
 pub struct JWT {
     encode_secret: EncodingKey,
     decode_secret: DecodingKey,
     header: Header,
     validation: Validation,
-    expiration: Duration,
+    /// 过期时间（秒），支持热加载
+    expiration: AtomicU64,
     audience: String,
     issuer: String,
 }
@@ -66,7 +103,7 @@ impl JWT {
         validation.set_audience(&[&config.aduience]);
         validation.set_issuer(&[&config.issuer]);
         validation.set_required_spec_claims(&["jti", "sub", "aud", "iss", "iat", "exp"]);
-        let expiration: Duration = config.exp;
+        let expiration = AtomicU64::new(config.exp.as_secs());
         let audience = config.aduience.to_string();
         let issuer = config.issuer.to_string();
         Self {
@@ -93,11 +130,15 @@ impl JWT {
             aud: self.audience.clone(),
             iss: self.issuer.clone(),
             iat: current_timestamp,
-            exp: current_timestamp.saturating_add(self.expiration.as_secs()),
+            exp: current_timestamp.saturating_add(self.expiration.load(Ordering::Relaxed)),
         };
         let token = jsonwebtoken::encode(&self.header, &claims, &self.encode_secret)?;
         Ok(token)
     }
+    pub fn set_expiration(&self, expiration: Duration) {
+        self.expiration
+            .store(expiration.as_secs(), Ordering::Relaxed);
+    }
     pub fn decode(&self, token: &str) -> anyhow::Result<Principal> {
         let token_data =
             jsonwebtoken::decode::<Claims>(token, &self.decode_secret, &self.validation)?;
@@ -0,0 +1,138 @@
+use axum::{
+    body::{Body, Bytes, HttpBody},
+    extract::Request,
+    http::{HeaderMap, header},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::app::{ApiError, redact};
+
+/// 按 `server.middleware.body_log` 配置记录 JSON 请求体和响应体，日志输出在 `api request` span 中，修改后热加载生效
+pub async fn body_log(request: Request, next: Next) -> Result<Response, ApiError> {
+    let (max_size, body_limit) = {
+        let config = crate::config::current();
+        let middleware = config.server().middleware();
+        let body_log = middleware.body_log();
+        let ratio = body_log.sample_ratio_for(request.uri().path());
+        if !body_log.enabled() || !sampled(ratio) {
+            return Ok(next.run(request).await);
+        }
+        let body_limit = middleware.body_limit();
+        (
+            body_log.max_size().as_u64() as usize,
+            body_limit
+                .enabled()
+                .then(|| body_limit.max_size().as_u64() as usize),
+        )
+    };
+    let request = log_request(request, max_size, body_limit).await?;
+    let response = next.run(request).await;
+    log_response(response, max_size).await
+}
+
+/// 只读取长度已知且不超过请求体大小限制的请求体，其余的交给后续的提取器处理
+async fn log_request(
+    request: Request,
+    max_size: usize,
+    body_limit: Option<usize>,
+) -> Result<Request, ApiError> {
+    let length = request.body().size_hint().exact();
+    let capturable =
+        length.is_some_and(|length| body_limit.is_none_or(|limit| length <= limit as u64));
+    if !is_json(request.headers()) || !capturable {
+        return Ok(request);
+    }
+    let (parts, body) = request.into_parts();
+    let bytes = axum::body::to_bytes(body, usize::MAX)
+        .await
+        .map_err(anyhow::Error::from)?;
+    tracing::info!(body = %preview(&bytes, max_size), "request body");
+    Ok(Request::from_parts(parts, Body::from(bytes)))
+}
+
+/// 流式响应（例如导出）长度未知，不记录
+async fn log_response(response: Response, max_size: usize) -> Result<Response, ApiError> {
+    if !is_json(response.headers()) || response.body().size_hint().exact().is_none() {
+        return Ok(response);
+    }
+    let (parts, body) = response.into_parts();
+    let bytes = axum::body::to_bytes(body, usize::MAX)
+        .await
+        .map_err(anyhow::Error::from)?;
+    tracing::info!(
+        status = parts.status.as_u16(),
+        body = %preview(&bytes, max_size),
+        "response body"
+    );
+    Ok(Response::from_parts(parts, Body::from(bytes)))
+}
+
+fn sampled(ratio: f64) -> bool {
+    ratio >= 1.0 || (ratio > 0.0 && rand::random::<f64>() < ratio)
+}
+
+/// `application/json` 和 `application/*+json`
+fn is_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map(|mime| mime.trim().to_ascii_lowercase())
+        .is_some_and(|mime| {
+            mime == "application/json"
+                || (mime.starts_with("application/") && mime.ends_with("+json"))
+        })
+}
+
+/// 先脱敏再截断，避免截断后的字段值无法被识别
+fn preview(bytes: &Bytes, max_size: usize) -> String {
+    let text = String::from_utf8_lossy(bytes);
+    let text = redact::redact(&text);
+    if text.len() <= max_size {
+        return text.into_owned();
+    }
+    let end = text.floor_char_boundary(max_size);
+    format!("{}...({} bytes)", &text[..end], bytes.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    fn content_type(value: &'static str) -> HeaderMap {
+        HeaderMap::from_iter([(header::CONTENT_TYPE, HeaderValue::from_static(value))])
+    }
+
+    #[test]
+    fn detects_json_content_types() {
+        assert!(is_json(&content_type("application/json")));
+        assert!(is_json(&content_type("Application/JSON; charset=utf-8")));
+        assert!(is_json(&content_type("application/problem+json")));
+        assert!(!is_json(&content_type("text/csv")));
+        assert!(!is_json(&content_type("multipart/form-data; boundary=x")));
+        assert!(!is_json(&HeaderMap::new()));
+    }
+
+    #[test]
+    fn preview_keeps_short_bodies() {
+        let body = Bytes::from_static(br#"{"id":"1"}"#);
+        assert_eq!(preview(&body, 64), r#"{"id":"1"}"#);
+    }
+
+    #[test]
+    fn preview_truncates_on_char_boundary() {
+        let body = Bytes::from("{\"name\":\"张三\"}");
+        // 第 10 个字节在“张”的中间
+        assert_eq!(preview(&body, 10), "{\"name\":\"...(17 bytes)");
+    }
+
+    #[test]
+    fn sampling_bounds() {
+        assert!(sampled(1.0));
+        assert!(!sampled(0.0));
+        assert!(!sampled(-1.0));
+    }
+}
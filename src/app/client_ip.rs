@@ -0,0 +1,227 @@
+use std::{
+    fmt,
+    net::{IpAddr, SocketAddr},
+};
+
+use axum::{
+    extract::{ConnectInfo, FromRequestParts},
+    http::{Extensions, HeaderMap, header, request::Parts},
+};
+use ipnet::IpNet;
+
+use crate::app::ApiError;
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+const X_REAL_IP: &str = "x-real-ip";
+
+/// 客户端真实 IP
+///
+/// 直连地址属于 `server.trusted_proxies` 时，依次读取 `Forwarded` / `X-Forwarded-For` / `X-Real-IP`，
+/// 从右向左跳过可信代理，取第一个不可信的地址；否则使用直连地址
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub IpAddr);
+
+impl fmt::Display for ClientIp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ClientIp {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        client_ip(&parts.headers, &parts.extensions)
+            .map(ClientIp)
+            .ok_or_else(|| {
+                ApiError::InternalServerError(anyhow::anyhow!(
+                    "ClientIp extractor requires ConnectInfo"
+                ))
+            })
+    }
+}
+
+/// 解析客户端 IP，供中间件和 span 使用，没有 `ConnectInfo` 时返回 None
+pub(crate) fn client_ip(headers: &HeaderMap, extensions: &Extensions) -> Option<IpAddr> {
+    let ConnectInfo(peer) = extensions.get::<ConnectInfo<SocketAddr>>()?;
+    let config = crate::config::current();
+    Some(resolve(
+        headers,
+        peer.ip(),
+        config.server().trusted_proxies(),
+    ))
+}
+
+fn resolve(headers: &HeaderMap, peer: IpAddr, trusted_proxies: &[IpNet]) -> IpAddr {
+    let is_trusted = |ip: &IpAddr| trusted_proxies.iter().any(|net| net.contains(ip));
+    let mut client = peer.to_canonical();
+    if !is_trusted(&client) {
+        return client;
+    }
+    for hop in forwarded_chain(headers).into_iter().rev() {
+        // 无法解析的地址（例如 unknown 或混淆标识）之前的记录不可信
+        let Some(ip) = hop else {
+            break;
+        };
+        client = ip.to_canonical();
+        if !is_trusted(&client) {
+            break;
+        }
+    }
+    client
+}
+
+/// 转发链，从客户端到最后一个代理
+fn forwarded_chain(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+    let forwarded = header_values(headers, header::FORWARDED.as_str())
+        .flat_map(|value| value.split(','))
+        .filter_map(|element| {
+            element.split(';').find_map(|pair| {
+                let (key, value) = pair.split_once('=')?;
+                key.trim()
+                    .eq_ignore_ascii_case("for")
+                    .then(|| parse_node(value.trim().trim_matches('"')))
+            })
+        })
+        .collect::<Vec<_>>();
+    if !forwarded.is_empty() {
+        return forwarded;
+    }
+    let x_forwarded_for = header_values(headers, X_FORWARDED_FOR)
+        .flat_map(|value| value.split(','))
+        .map(|value| parse_node(value.trim()))
+        .collect::<Vec<_>>();
+    if !x_forwarded_for.is_empty() {
+        return x_forwarded_for;
+    }
+    header_values(headers, X_REAL_IP)
+        .map(|value| parse_node(value.trim()))
+        .collect()
+}
+
+fn header_values<'a>(headers: &'a HeaderMap, name: &str) -> impl Iterator<Item = &'a str> {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+}
+
+/// 支持 `1.2.3.4`、`1.2.3.4:80`、`2001:db8::1` 和 `[2001:db8::1]:80`
+fn parse_node(value: &str) -> Option<IpAddr> {
+    if let Some(rest) = value.strip_prefix('[') {
+        let (ip, _) = rest.split_once(']')?;
+        return ip.parse().ok();
+    }
+    value
+        .parse::<IpAddr>()
+        .ok()
+        .or_else(|| value.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    fn header_map(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    fn trusted() -> Vec<IpNet> {
+        vec![
+            "127.0.0.1/32".parse().unwrap(),
+            "10.0.0.0/8".parse().unwrap(),
+        ]
+    }
+
+    #[test]
+    fn untrusted_peer_ignores_forwarded_headers() {
+        let headers = header_map(&[(X_FORWARDED_FOR, "1.1.1.1"), ("forwarded", "for=2.2.2.2")]);
+        assert_eq!(resolve(&headers, ip("8.8.8.8"), &trusted()), ip("8.8.8.8"));
+    }
+
+    #[test]
+    fn trusted_hops_are_skipped_right_to_left() {
+        // 客户端伪造的最左侧地址不会被采用
+        let headers = header_map(&[(X_FORWARDED_FOR, "6.6.6.6, 1.1.1.1, 10.0.0.2, 10.0.0.1")]);
+        assert_eq!(
+            resolve(&headers, ip("127.0.0.1"), &trusted()),
+            ip("1.1.1.1")
+        );
+    }
+
+    #[test]
+    fn all_trusted_hops_use_leftmost() {
+        let headers = header_map(&[(X_FORWARDED_FOR, "10.0.0.3"), (X_FORWARDED_FOR, "10.0.0.2")]);
+        assert_eq!(
+            resolve(&headers, ip("127.0.0.1"), &trusted()),
+            ip("10.0.0.3")
+        );
+    }
+
+    #[test]
+    fn forwarded_takes_precedence() {
+        let headers = header_map(&[
+            (
+                "forwarded",
+                r#"for=1.1.1.1;proto=https, for="[2001:db8::1]:8080""#,
+            ),
+            (X_FORWARDED_FOR, "3.3.3.3"),
+            (X_REAL_IP, "4.4.4.4"),
+        ]);
+        assert_eq!(
+            resolve(&headers, ip("127.0.0.1"), &trusted()),
+            ip("2001:db8::1")
+        );
+    }
+
+    #[test]
+    fn unknown_forwarded_node_stops_the_chain() {
+        let headers = header_map(&[("forwarded", "for=1.1.1.1, for=unknown, for=10.0.0.1")]);
+        assert_eq!(
+            resolve(&headers, ip("127.0.0.1"), &trusted()),
+            ip("10.0.0.1")
+        );
+        let headers = header_map(&[("forwarded", "for=1.1.1.1, for=_hidden")]);
+        assert_eq!(
+            resolve(&headers, ip("127.0.0.1"), &trusted()),
+            ip("127.0.0.1")
+        );
+    }
+
+    #[test]
+    fn real_ip_is_used_last() {
+        let headers = header_map(&[(X_REAL_IP, "4.4.4.4")]);
+        assert_eq!(resolve(&headers, ip("10.1.2.3"), &trusted()), ip("4.4.4.4"));
+    }
+
+    #[test]
+    fn ipv4_mapped_peer_is_canonicalized() {
+        let headers = header_map(&[(X_FORWARDED_FOR, "1.1.1.1")]);
+        assert_eq!(
+            resolve(&headers, ip("::ffff:127.0.0.1"), &trusted()),
+            ip("1.1.1.1")
+        );
+    }
+
+    #[test]
+    fn parse_node_formats() {
+        assert_eq!(parse_node("1.2.3.4"), Some(ip("1.2.3.4")));
+        assert_eq!(parse_node("1.2.3.4:80"), Some(ip("1.2.3.4")));
+        assert_eq!(parse_node("2001:db8::1"), Some(ip("2001:db8::1")));
+        assert_eq!(parse_node("[::1]:80"), Some(ip("::1")));
+        assert_eq!(parse_node("[::1]"), Some(ip("::1")));
+        assert_eq!(parse_node("unknown"), None);
+        assert_eq!(parse_node("_obfuscated"), None);
+        assert_eq!(parse_node("[::1"), None);
+    }
+}
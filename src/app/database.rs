@@ -1,37 +1,343 @@
-use std::{cmp::max, time::Duration};
+use std::{
+    future::Future,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
 
+use futures::future::BoxFuture;
+use rand::Rng;
 use sea_orm::{ConnectOptions, ConnectionTrait, Database, DatabaseConnection, Statement};
 use tracing::info;
 
-// Import your config getter (adjust the path as needed)
-// use crate::config::get;
+use crate::{
+    app::{
+        health::{HealthCheck, HealthDetails, Readiness},
+        metrics, redact, telemetry,
+    },
+    config::database::{RetryConfig, SlowQueryConfig},
+    migration::{Migrator, MigratorTrait},
+};
 
-/** */
+/// 主库和只读副本连接
+///
+/// 写操作以及需要读到最新数据的查询使用 [`Db::write`]，列表等查询使用 [`Db::read`]：
+/// 轮询选择健康的副本，没有配置副本或副本全部不可用时回退到主库
+#[derive(Clone)]
+pub struct Db {
+    primary: DatabaseConnection,
+    replicas: Arc<[Replica]>,
+    next: Arc<AtomicUsize>,
+}
+
+struct Replica {
+    conn: DatabaseConnection,
+    healthy: AtomicBool,
+}
+
+impl Db {
+    pub fn new(primary: DatabaseConnection) -> Self {
+        Self {
+            primary,
+            replicas: Arc::new([]),
+            next: Arc::default(),
+        }
+    }
+    pub fn write(&self) -> &DatabaseConnection {
+        &self.primary
+    }
+    pub fn read(&self) -> &DatabaseConnection {
+        let len = self.replicas.len();
+        if len == 0 {
+            return &self.primary;
+        }
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        (0..len)
+            .map(|offset| &self.replicas[(start + offset) % len])
+            .find(|replica| replica.healthy.load(Ordering::Acquire))
+            .map(|replica| &replica.conn)
+            .unwrap_or(&self.primary)
+    }
+    /// 主库和副本的连接，名称为 primary / replica-{index}
+    pub fn connections(&self) -> impl Iterator<Item = (String, &DatabaseConnection)> {
+        std::iter::once((String::from("primary"), &self.primary)).chain(
+            self.replicas
+                .iter()
+                .enumerate()
+                .map(|(index, replica)| (format!("replica-{index}"), &replica.conn)),
+        )
+    }
+    /// 关闭主库和所有副本的连接池
+    pub async fn close(&self) -> anyhow::Result<()> {
+        for replica in self.replicas.iter() {
+            replica.conn.clone().close().await?;
+        }
+        self.primary.clone().close().await?;
+        info!("Database connections closed");
+        Ok(())
+    }
+    /// 只读副本健康检查，没有配置副本时返回 None
+    pub fn replica_check(&self) -> Option<impl HealthCheck + 'static> {
+        (!self.replicas.is_empty()).then(|| ReplicaCheck {
+            replicas: self.replicas.clone(),
+        })
+    }
+}
+
+/// 汇报后台检查得到的副本状态，副本全部不可用时读请求回退主库，因此不是关键检查
+struct ReplicaCheck {
+    replicas: Arc<[Replica]>,
+}
+
+impl HealthCheck for ReplicaCheck {
+    fn name(&self) -> &'static str {
+        "replicas"
+    }
+    fn critical(&self) -> bool {
+        false
+    }
+    fn check(&self) -> BoxFuture<'_, anyhow::Result<HealthDetails>> {
+        Box::pin(async move {
+            let healthy = self
+                .replicas
+                .iter()
+                .filter(|replica| replica.healthy.load(Ordering::Acquire))
+                .count();
+            if healthy == 0 {
+                anyhow::bail!("all replicas are down, reads fall back to the primary");
+            }
+            Ok(HealthDetails::from([
+                ("healthy", healthy as u64),
+                ("total", self.replicas.len() as u64),
+            ]))
+        })
+    }
+}
+
+/// 懒连接所有只读副本，并在后台定期检查副本健康状态
+///
+/// 副本在第一次健康检查通过前不会被使用，不可用的副本不影响服务启动
+pub async fn init_replicas(primary: DatabaseConnection) -> anyhow::Result<Db> {
+    let database_config = crate::config::get().database();
+    let mut replicas = Vec::new();
+    for url in database_config.replica_urls()? {
+        let mut opt = connect_options(url);
+        opt.connect_lazy(true);
+        replicas.push(Replica {
+            conn: connect(opt).await?,
+            healthy: AtomicBool::new(false),
+        });
+    }
+    let db = Db {
+        replicas: replicas.into(),
+        ..Db::new(primary)
+    };
+    if !db.replicas.is_empty() {
+        info!("Database read replicas: {}", db.replicas.len());
+        tokio::spawn(check_replicas(
+            db.replicas.clone(),
+            database_config.replica_health_check_interval(),
+        ));
+    }
+    Ok(db)
+}
+
+async fn check_replicas(replicas: Arc<[Replica]>, interval: Duration) {
+    let mut interval = tokio::time::interval(interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        for (index, replica) in replicas.iter().enumerate() {
+            let result = with_connect_timeout(replica.conn.ping()).await;
+            let healthy = result.is_ok();
+            if replica.healthy.swap(healthy, Ordering::AcqRel) != healthy {
+                match result {
+                    Ok(()) => info!("Database replica {} is up", index),
+                    Err(err) => tracing::warn!("Database replica {} is down: {:#}", index, err),
+                }
+            }
+        }
+    }
+}
+
+/// 连接数据库，失败时按配置指数退避重试，超过最大等待时间后返回错误
 pub async fn init() -> anyhow::Result<DatabaseConnection> {
     let database_config = crate::config::get().database();
-    let url = format!(
-        "postgres://{}:{}@{}:{}/{}",
-        database_config.username(),
-        database_config.password(),
-        database_config.host(),
-        database_config.port(),
-        database_config.database()
+    let opt = connect_options(database_config.connection_url()?);
+    let conn = retry(database_config.retry(), false, || {
+        with_connect_timeout(async {
+            let conn = connect(opt.clone()).await?;
+            conn.ping().await?;
+            Ok(conn)
+        })
+    })
+    .await?;
+    info!("Database connected");
+    log_database_version(&conn).await?;
+    Ok(conn)
+}
+
+/// 服务启动时连接数据库
+///
+/// 开启 `database.retry.start_degraded` 时先返回懒连接，HTTP 服务以未就绪状态启动，
+/// 后台持续重连，连接成功后执行自动迁移并标记为就绪
+pub async fn init_for_server(readiness: Readiness) -> anyhow::Result<DatabaseConnection> {
+    let database_config = crate::config::get().database();
+    if !database_config.retry().start_degraded() {
+        let conn = init().await?;
+        auto_migrate(&conn).await?;
+        readiness.set_database_ready(true);
+        return Ok(conn);
+    }
+    let mut opt = connect_options(database_config.connection_url()?);
+    opt.connect_lazy(true);
+    let conn = connect(opt).await?;
+    tokio::spawn({
+        let conn = conn.clone();
+        async move {
+            let connected = retry(database_config.retry(), true, || {
+                with_connect_timeout(conn.ping())
+            })
+            .await;
+            let ready = match connected {
+                Ok(()) => {
+                    info!("Database connected");
+                    let _ = log_database_version(&conn).await;
+                    auto_migrate(&conn).await
+                }
+                Err(err) => Err(err),
+            };
+            match ready {
+                Ok(()) => readiness.set_database_ready(true),
+                Err(err) => tracing::error!("Database is not ready: {:#}", err),
+            }
+        }
+    });
+    tracing::warn!("Starting in degraded mode, waiting for the database in background");
+    Ok(conn)
+}
+
+/// 慢 SQL 阈值（纳秒），`u64::MAX` 表示未开启；创建连接和配置热加载时更新，避免每条 SQL 都读取配置
+static SLOW_QUERY_THRESHOLD: AtomicU64 = AtomicU64::new(u64::MAX);
+
+/// 按 `database.slow_query` 配置更新慢 SQL 阈值
+pub fn set_slow_query(config: &SlowQueryConfig) {
+    let threshold = if config.enabled() {
+        u64::try_from(config.threshold().as_nanos()).unwrap_or(u64::MAX)
+    } else {
+        u64::MAX
+    };
+    SLOW_QUERY_THRESHOLD.store(threshold, Ordering::Relaxed);
+}
+
+/// 创建连接，开启链路追踪时每条 SQL 记录为当前请求 span 的子 span
+async fn connect(opt: ConnectOptions) -> Result<DatabaseConnection, sea_orm::DbErr> {
+    set_slow_query(crate::config::current().database().slow_query());
+    let mut conn = Database::connect(opt).await?;
+    conn.set_metric_callback(on_query);
+    Ok(conn)
+}
+
+/// 每条 SQL 执行完成后调用：导出 span，超过阈值时记录慢 SQL（热加载生效）
+fn on_query(info: &sea_orm::metric::Info<'_>) {
+    telemetry::record_query(info);
+    if !is_slow(info.elapsed, SLOW_QUERY_THRESHOLD.load(Ordering::Relaxed)) {
+        return;
+    }
+    let statement = &info.statement;
+    metrics::record_slow_query(&telemetry::query_name(&statement.sql));
+    // 参数值可能包含密码、手机号等，只输出参数个数
+    tracing::warn!(
+        elapsed_ms = info.elapsed.as_millis() as u64,
+        sql = %redact::redact(&statement.sql),
+        params = statement.values.as_ref().map_or(0, |values| values.0.len()),
+        failed = info.failed,
+        "slow query"
     );
+}
+
+fn is_slow(elapsed: Duration, threshold_nanos: u64) -> bool {
+    threshold_nanos != u64::MAX && elapsed.as_nanos() >= u128::from(threshold_nanos)
+}
+
+fn connect_options(url: String) -> ConnectOptions {
+    let database_config = crate::config::get().database();
     let mut opt = ConnectOptions::new(url);
-    let cpu_counter = num_cpus::get() as u32;
     opt.connect_timeout(std::time::Duration::from_secs(database_config.timeout()))
-        .acquire_timeout(Duration::from_secs(30))
-        .idle_timeout(Duration::from_secs(300))
-        .max_lifetime(Duration::from_secs(3600 * 24))
-        .max_connections(max(cpu_counter * 8, 20))
-        .min_connections(max(cpu_counter * 4, 10))
-        .sqlx_logging(false)
+        .acquire_timeout(database_config.acquire_timeout())
+        .idle_timeout(database_config.idle_timeout())
+        .max_lifetime(database_config.max_lifetime())
+        .max_connections(database_config.max_connections())
+        .min_connections(database_config.min_connections())
+        .sqlx_logging(database_config.sqlx_logging())
+        .sqlx_logging_level(database_config.sqlx_logging_level())
         .set_schema_search_path(database_config.schema());
-    let conn = Database::connect(opt).await?;
-    conn.ping().await?;
-    info!("Database connected");
-    log_database_version(&conn).await?;
-    Ok(conn)
+    opt
+}
+
+/// 连接池获取连接时会在 acquire_timeout 内持续重试，这里按 database.timeout 限制单次尝试的时长
+async fn with_connect_timeout<T>(
+    future: impl Future<Output = Result<T, sea_orm::DbErr>>,
+) -> anyhow::Result<T> {
+    let timeout = Duration::from_secs(crate::config::get().database().timeout());
+    tokio::time::timeout(timeout, future)
+        .await
+        .map_err(|_| anyhow::anyhow!("connection timed out after {:?}", timeout))?
+        .map_err(anyhow::Error::from)
+}
+
+async fn auto_migrate(conn: &DatabaseConnection) -> anyhow::Result<()> {
+    if crate::config::get().database().auto_migrate() {
+        info!("Running pending database migrations...");
+        Migrator::up(conn, None).await?;
+    }
+    Ok(())
+}
+
+/// 指数退避重试，`unbounded` 为 true 时忽略最大尝试次数和最大等待时间
+async fn retry<T, E, F, Fut>(config: &RetryConfig, unbounded: bool, mut f: F) -> anyhow::Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: Into<anyhow::Error>,
+{
+    let started = Instant::now();
+    let mut backoff = config.initial_backoff();
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let err = match f().await {
+            Ok(value) => return Ok(value),
+            Err(err) => err.into(),
+        };
+        let elapsed = started.elapsed();
+        let exhausted = (config.max_attempts() > 0 && attempt >= config.max_attempts())
+            || elapsed >= config.max_wait();
+        if exhausted && !unbounded {
+            tracing::error!(
+                "Database connection attempt {} failed, giving up after {:?}: {:#}",
+                attempt,
+                elapsed,
+                err
+            );
+            return Err(err.context("Failed to connect to database"));
+        }
+        // 在 [backoff/2, backoff] 之间随机，避免多个实例同时重连
+        let mut delay = rand::thread_rng().gen_range(backoff / 2..=backoff);
+        if !unbounded {
+            delay = delay.min(config.max_wait().saturating_sub(elapsed));
+        }
+        tracing::warn!(
+            "Database connection attempt {} failed, retrying in {} ms: {:#}",
+            attempt,
+            delay.as_millis(),
+            err
+        );
+        tokio::time::sleep(delay).await;
+        backoff = (backoff * 2).min(config.max_backoff());
+    }
 }
 
 /**
@@ -47,3 +353,16 @@ async fn log_database_version(conn: &DatabaseConnection) -> anyhow::Result<()> {
     info!("Database version:{}", version);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slow_query_threshold_is_inclusive() {
+        let threshold = Duration::from_millis(500).as_nanos() as u64;
+        assert!(!is_slow(Duration::from_millis(499), threshold));
+        assert!(is_slow(Duration::from_millis(500), threshold));
+        assert!(!is_slow(Duration::from_secs(3600), u64::MAX));
+    }
+}
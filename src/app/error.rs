@@ -1,5 +1,8 @@
 use axum::{
-    extract::rejection::{JsonRejection, PathRejection, QueryRejection},
+    extract::{
+        multipart::{MultipartError, MultipartRejection},
+        rejection::{JsonRejection, PathRejection, QueryRejection},
+    },
     http::StatusCode,
     response::{IntoResponse, Response},
 };
@@ -18,7 +21,13 @@ pub enum ApiError {
     Biz(ResponseErrorCode),
 
     #[error("database exception:{0}")]
-    DatabaseError(#[from] sea_orm::DbErr),
+    DatabaseError(sea_orm::DbErr),
+
+    #[error("conflict:{}", .code.message())]
+    Conflict {
+        code: ResponseErrorCode,
+        field: Option<&'static str>,
+    },
 
     #[error("Internal Server Error {0}")]
     InternalServerError(#[from] anyhow::Error),
@@ -32,6 +41,12 @@ pub enum ApiError {
     #[error("body param error:{0}")]
     JsonError(#[from] JsonRejection),
 
+    #[error("multipart param error:{0}")]
+    MultipartError(#[from] MultipartRejection),
+
+    #[error("multipart field error:{0}")]
+    MultipartFieldError(#[from] MultipartError),
+
     #[error("validation error:{0}")]
     ValidationError(String),
 
@@ -42,6 +57,51 @@ pub enum ApiError {
 
     #[error("unauthenticated:{0}")]
     Unauthenticated(String),
+
+    #[error("forbidden:{0}")]
+    Forbidden(String),
+
+    #[error("service unavailable:{0}")]
+    ServiceUnavailable(String),
+
+    #[error("request timeout")]
+    RequestTimeout,
+
+    #[error("too many requests")]
+    TooManyRequests,
+}
+
+/// 唯一约束名 -> (字段, 错误码)，用于把数据库唯一约束冲突转换为 409，索引由 `migration::m20261019_000001_create_sys_user_table` 创建
+const UNIQUE_CONSTRAINTS: &[(&str, &str, ResponseErrorCode)] = &[
+    (
+        "uk_sys_user_account",
+        "account",
+        ResponseErrorCode::AccountAlreadyExists,
+    ),
+    (
+        "uk_sys_user_mobile_phone",
+        "mobile_phone",
+        ResponseErrorCode::MobilePhoneAlreadyExists,
+    ),
+];
+
+impl From<sea_orm::DbErr> for ApiError {
+    fn from(err: sea_orm::DbErr) -> Self {
+        match err.sql_err() {
+            Some(sea_orm::SqlErr::UniqueConstraintViolation(message)) => UNIQUE_CONSTRAINTS
+                .iter()
+                .find(|(constraint, _, _)| message.contains(constraint))
+                .map(|(_, field, code)| ApiError::Conflict {
+                    code: *code,
+                    field: Some(field),
+                })
+                .unwrap_or(ApiError::Conflict {
+                    code: ResponseErrorCode::DataAlreadyExists,
+                    field: None,
+                }),
+            _ => ApiError::DatabaseError(err),
+        }
+    }
 }
 
 impl From<axum_valid::ValidRejection<ApiError>> for ApiError {
@@ -66,9 +126,16 @@ impl ApiError {
             ApiError::ParameterError(_)
             | ApiError::PathError(_)
             | ApiError::JsonError(_)
+            | ApiError::MultipartError(_)
+            | ApiError::MultipartFieldError(_)
             | ApiError::ValidationError(_)
             | ApiError::Biz { .. } => StatusCode::BAD_REQUEST,
+            ApiError::Conflict { .. } => StatusCode::CONFLICT,
             ApiError::Jwt(_) | ApiError::Unauthenticated(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::RequestTimeout => StatusCode::REQUEST_TIMEOUT,
+            ApiError::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
         }
     }
 }
@@ -78,17 +145,32 @@ impl IntoResponse for ApiError {
         let status_code = self.status_code();
         tracing::error!("{}", &self);
 
-        let body = match &self {
-            ApiError::Biz(error_code) => axum::Json(AppResponse::<()>::fail(
-                error_code.code() as i32,
-                error_code.message(),
-            )),
-            _ => axum::Json(AppResponse::<()>::fail_enum(&self)),
-        };
-        (status_code, body).into_response()
+        match &self {
+            ApiError::Biz(error_code) => (
+                status_code,
+                AppResponse::<()>::fail(error_code.code() as i32, error_code.message()),
+            )
+                .into_response(),
+            ApiError::Conflict { code, field } => (
+                status_code,
+                AppResponse::fail_with_data(
+                    code.code() as i32,
+                    code.message(),
+                    ConflictData { field: *field },
+                ),
+            )
+                .into_response(),
+            _ => (status_code, AppResponse::<()>::fail_enum(&self)).into_response(),
+        }
     }
 }
 
+#[derive(Debug, Serialize)]
+struct ConflictData {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    field: Option<&'static str>,
+}
+
 macro_rules! define_error_codes {
     (
         $(#[$meta:meta])*
@@ -123,6 +205,13 @@ define_error_codes! {
         FindNotUser(5001, "找不到用户"),
         DbPwdNotFind(5002, "数据库密码未找到"),
         UserNameOrPasswordError(5003, "用户名或密码错误"),
+        ImportFileNotFound(5004, "导入文件不能为空"),
+        ImportFileFormatUnsupported(5005, "导入文件格式不支持，仅支持 csv/xlsx"),
+        BatchSizeExceeded(5006, "批量操作数量超出限制"),
+        AccountAlreadyExists(5007, "账号已存在"),
+        MobilePhoneAlreadyExists(5008, "手机号已存在"),
+        DataAlreadyExists(5009, "数据已存在"),
+        ExportSizeExceeded(5010, "导出数量超出 xlsx 限制，请使用 csv 格式或缩小查询范围"),
         // Add more error codes as needed
     }
 }
@@ -0,0 +1,224 @@
+use std::{
+    collections::BTreeMap,
+    sync::{
+        Arc, PoisonError, RwLock,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use axum::{
+    Router,
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use futures::future::{BoxFuture, join_all};
+use serde::Serialize;
+
+use crate::app::{ApiError, AppResponse, AppState};
+
+/// 健康检查的附加信息，例如连接池统计
+pub type HealthDetails = BTreeMap<&'static str, u64>;
+
+/// 可插拔的健康检查，子系统通过 [`Readiness::register`] 注册后会出现在 `/health/ready` 中
+pub trait HealthCheck: Send + Sync {
+    fn name(&self) -> &'static str;
+    /// 关键检查失败时服务整体不可用（503），非关键检查只在结果中标记为 down
+    fn critical(&self) -> bool {
+        true
+    }
+    fn check(&self) -> BoxFuture<'_, anyhow::Result<HealthDetails>>;
+}
+
+/// 服务就绪状态，数据库未连接时服务处于未就绪（降级）状态
+#[derive(Clone, Default)]
+pub struct Readiness {
+    database_ready: Arc<AtomicBool>,
+    shutting_down: Arc<AtomicBool>,
+    checks: Arc<RwLock<Vec<Arc<dyn HealthCheck>>>>,
+}
+
+impl Readiness {
+    pub fn is_ready(&self) -> bool {
+        self.database_ready.load(Ordering::Acquire)
+    }
+    pub fn set_database_ready(&self, ready: bool) {
+        self.database_ready.store(ready, Ordering::Release);
+    }
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::Acquire)
+    }
+    /// 开始停机，之后 `/health/ready` 始终返回 503，让负载均衡摘除流量
+    pub fn begin_shutdown(&self) {
+        self.shutting_down.store(true, Ordering::Release);
+    }
+    pub fn register(&self, check: impl HealthCheck + 'static) {
+        self.checks
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Arc::new(check));
+    }
+    fn checks(&self) -> Vec<Arc<dyn HealthCheck>> {
+        self.checks
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthVO {
+    pub status: HealthStatus,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub checks: BTreeMap<&'static str, CheckVO>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckVO {
+    pub status: HealthStatus,
+    pub critical: bool,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub details: HealthDetails,
+}
+
+/// 健康检查路由，不经过认证和就绪检查
+pub fn create_router() -> Router<AppState> {
+    Router::new()
+        .route("/health/live", get(live))
+        .route("/health/ready", get(ready))
+}
+
+/// 存活检查：进程能够处理请求即为存活
+async fn live() -> AppResponse<HealthVO> {
+    AppResponse::ok(Some(HealthVO {
+        status: HealthStatus::Up,
+        checks: BTreeMap::new(),
+    }))
+}
+
+/// 就绪检查：检查数据库连接和所有注册的健康检查，任一关键检查失败或停机中返回 503
+async fn ready(State(state): State<AppState>) -> Response {
+    if state.readiness.is_shutting_down() {
+        return unavailable(
+            "service is shutting down",
+            HealthVO {
+                status: HealthStatus::Down,
+                checks: BTreeMap::new(),
+            },
+        );
+    }
+    let timeout = crate::config::current().server().health_check_timeout();
+    let database = DatabaseCheck {
+        state: state.clone(),
+    };
+    let registered = state.readiness.checks();
+    let checks = std::iter::once(&database as &dyn HealthCheck)
+        .chain(registered.iter().map(|check| check.as_ref()));
+    let results = join_all(checks.map(|check| run_check(check, timeout))).await;
+    let healthy = results
+        .iter()
+        .all(|(_, result)| !result.critical || result.status == HealthStatus::Up);
+    let health = HealthVO {
+        status: if healthy {
+            HealthStatus::Up
+        } else {
+            HealthStatus::Down
+        },
+        checks: results.into_iter().collect(),
+    };
+    if healthy {
+        AppResponse::ok(Some(health)).into_response()
+    } else {
+        unavailable("service unavailable", health)
+    }
+}
+
+fn unavailable(message: &str, health: HealthVO) -> Response {
+    let status_code = StatusCode::SERVICE_UNAVAILABLE;
+    (
+        status_code,
+        AppResponse::fail_with_data(status_code.as_u16() as i32, message, health),
+    )
+        .into_response()
+}
+
+async fn run_check(check: &dyn HealthCheck, timeout: Duration) -> (&'static str, CheckVO) {
+    let started = Instant::now();
+    let result = tokio::time::timeout(timeout, check.check())
+        .await
+        .unwrap_or_else(|_| Err(anyhow::anyhow!("timed out after {:?}", timeout)));
+    let (status, message, details) = match result {
+        Ok(details) => (HealthStatus::Up, None, details),
+        Err(err) => (
+            HealthStatus::Down,
+            Some(format!("{err:#}")),
+            HealthDetails::new(),
+        ),
+    };
+    let result = CheckVO {
+        status,
+        critical: check.critical(),
+        latency_ms: started.elapsed().as_millis() as u64,
+        message,
+        details,
+    };
+    (check.name(), result)
+}
+
+/// 主库连接检查，附带连接池统计
+struct DatabaseCheck {
+    state: AppState,
+}
+
+impl HealthCheck for DatabaseCheck {
+    fn name(&self) -> &'static str {
+        "database"
+    }
+    fn check(&self) -> BoxFuture<'_, anyhow::Result<HealthDetails>> {
+        Box::pin(async move {
+            if !self.state.readiness.is_ready() {
+                anyhow::bail!("database is not ready");
+            }
+            let conn = self.state.db.write();
+            conn.ping().await?;
+            let pool = conn.get_postgres_connection_pool();
+            Ok(HealthDetails::from([
+                ("poolSize", pool.size() as u64),
+                ("poolIdle", pool.num_idle() as u64),
+                (
+                    "poolMaxConnections",
+                    pool.options().get_max_connections() as u64,
+                ),
+            ]))
+        })
+    }
+}
+
+/// 未就绪时直接返回 503，避免请求在数据库不可用时排队等待连接
+pub async fn require_ready(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    if !state.readiness.is_ready() {
+        return Err(ApiError::ServiceUnavailable(String::from(
+            "database is not ready",
+        )));
+    }
+    Ok(next.run(request).await)
+}
@@ -0,0 +1,35 @@
+use std::task::{Context, Poll};
+
+use axum::extract::FromRef;
+use futures::future::BoxFuture;
+use tower::Service;
+
+use crate::app::{AppState, telemetry};
+
+/// 调用外部 HTTP 服务的客户端，发送请求时写入当前 span 的 `traceparent` / `tracestate`
+///
+/// handler 中通过 `State(client): State<HttpClient>` 获取，配合 `ServiceExt::oneshot` 发送请求
+#[derive(Debug, Clone, Default)]
+pub struct HttpClient(reqwest::Client);
+
+impl Service<reqwest::Request> for HttpClient {
+    type Response = reqwest::Response;
+    type Error = reqwest::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, mut request: reqwest::Request) -> Self::Future {
+        telemetry::inject_headers(request.headers_mut());
+        let client = self.0.clone();
+        Box::pin(async move { client.execute(request).await })
+    }
+}
+
+impl FromRef<AppState> for HttpClient {
+    fn from_ref(state: &AppState) -> Self {
+        state.http_client.clone()
+    }
+}
@@ -14,7 +14,7 @@ impl<T> OnResponse<T> for LatencyOnResponse {
     ) {
         let latency = Latency(latency);
         let status = response.status().as_u16();
-        span.record("status", &tracing::field::display(status));
+        span.record("status", tracing::field::display(status));
         tracing::info!(latency=%latency,status=%status,"finshied processing request");
     }
 }
@@ -1,15 +1,140 @@
-use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
+use std::sync::{Mutex, OnceLock, PoisonError};
 
-pub fn init() {
+use anyhow::Context;
+use rolling_file::{BasicRollingFileAppender, RollingConditionBasic};
+use tracing::Subscriber;
+use tracing_appender::non_blocking::{NonBlocking, WorkerGuard};
+use tracing_subscriber::{
+    EnvFilter, Layer, Registry, fmt::MakeWriter, layer::SubscriberExt, registry::LookupSpan,
+    reload, util::SubscriberInitExt,
+};
+
+use crate::{
+    app::redact::{self, RedactWriter},
+    config::logging::{LogFileConfig, LogFormat, LogRotation},
+};
+
+static FILTER_HANDLE: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+/// 当前生效的 EnvFilter
+static CURRENT_FILTER: Mutex<String> = Mutex::new(String::new());
+/// 日志文件后台写入线程，drop 时写完剩余日志
+static FILE_GUARD: Mutex<Option<WorkerGuard>> = Mutex::new(None);
+
+pub fn init() -> anyhow::Result<()> {
+    let config = crate::config::current();
+    let logging = config.logging();
+    // RUST_LOG 优先，其次是配置文件中的 logging.level 和 logging.targets
+    let env_filter = std::env::var(EnvFilter::DEFAULT_ENV).ok();
+    let from_env = env_filter.is_some();
+    let filter = env_filter.unwrap_or_else(|| logging.filter());
+    let (filter_layer, handle) = reload::Layer::new(EnvFilter::try_new(&filter)?);
+    redact::init(logging.redact())?;
+    let file_layer = logging
+        .file()
+        .map(|file| {
+            file_writer(file)
+                .map(|writer| fmt_layer(logging.file_format(), RedactWriter(writer), false))
+        })
+        .transpose()?;
     tracing_subscriber::registry()
-        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
-        .with(
-            tracing_subscriber::fmt::layer()
-                .with_file(true)
-                .with_line_number(true)
-                .with_thread_ids(true)
-                .with_thread_names(true)
-                .with_target(false),
-        )
+        .with(filter_layer)
+        .with(fmt_layer(
+            logging.format(),
+            RedactWriter(std::io::stdout),
+            true,
+        ))
+        .with(file_layer)
+        .with(super::telemetry::layer()?)
         .init();
+    let _ = FILTER_HANDLE.set(handle);
+    *CURRENT_FILTER
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner) = filter;
+    crate::config::on_reload(|config| {
+        if let Err(err) = redact::init(config.logging().redact()) {
+            tracing::error!("Failed to reload log redaction: {:#}", err);
+        }
+    });
+    if !from_env {
+        crate::config::on_reload(|config| {
+            if let Err(err) = set_filter(&config.logging().filter()) {
+                tracing::error!("Failed to change log level: {:#}", err);
+            }
+        });
+    }
+    Ok(())
+}
+
+fn fmt_layer<S, W>(format: LogFormat, writer: W, ansi: bool) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+    W: for<'writer> MakeWriter<'writer> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer()
+        .with_writer(writer)
+        .with_ansi(ansi)
+        .with_file(true)
+        .with_line_number(true)
+        .with_thread_ids(true)
+        .with_thread_names(true)
+        .with_target(false);
+    match format {
+        LogFormat::Pretty => layer.boxed(),
+        LogFormat::Json => layer
+            .json()
+            .with_current_span(true)
+            .with_span_list(false)
+            .boxed(),
+    }
+}
+
+fn file_writer(config: &LogFileConfig) -> anyhow::Result<NonBlocking> {
+    let path = config.path();
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create log directory {}", dir.display()))?;
+    }
+    let mut condition = match config.rotation() {
+        LogRotation::Daily => RollingConditionBasic::new().daily(),
+        LogRotation::Hourly => RollingConditionBasic::new().hourly(),
+        LogRotation::Never => RollingConditionBasic::new(),
+    };
+    if let Some(max_size) = config.max_size() {
+        condition = condition.max_size(max_size.as_u64());
+    }
+    let appender = BasicRollingFileAppender::new(&path, condition, config.max_files())
+        .with_context(|| format!("Failed to open log file {}", path.display()))?;
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+    *FILE_GUARD.lock().unwrap_or_else(PoisonError::into_inner) = Some(guard);
+    Ok(writer)
+}
+
+/// 运行时修改日志级别（EnvFilter 语法），配置文件热加载时会重新覆盖
+pub fn set_filter(filter: &str) -> anyhow::Result<()> {
+    let handle = FILTER_HANDLE.get().context("Logger is not initialized")?;
+    let env_filter = EnvFilter::try_new(filter)?;
+    handle.reload(env_filter)?;
+    *CURRENT_FILTER
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner) = filter.to_string();
+    tracing::info!("Log level changed to {}", filter);
+    Ok(())
+}
+
+pub fn current_filter() -> String {
+    CURRENT_FILTER
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// 写完日志文件中缓冲的日志
+pub async fn flush() -> anyhow::Result<()> {
+    let guard = FILE_GUARD
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take();
+    // drop 时会阻塞等待后台线程写完
+    tokio::task::spawn_blocking(move || drop(guard)).await?;
+    Ok(())
 }
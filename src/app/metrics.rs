@@ -0,0 +1,326 @@
+use std::{
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    Router,
+    extract::{MatchedPath, Request, State},
+    http::{HeaderMap, Method, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+
+use crate::{
+    app::{ApiError, AppState},
+    utils::crypt::constant_time_eq,
+};
+
+/// 没有匹配到路由的请求统一使用该标签，避免任意路径导致指标基数膨胀
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    http_requests_in_flight: IntGauge,
+    db_pool_connections: IntGaugeVec,
+    db_pool_idle_connections: IntGaugeVec,
+    db_pool_max_connections: IntGaugeVec,
+    db_transaction_begin_duration: Histogram,
+    logins: IntCounterVec,
+    slow_requests: IntCounterVec,
+    slow_queries: IntCounterVec,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new();
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "Total number of HTTP requests"),
+            &["method", "route", "status"],
+        )
+        .expect("invalid metric http_requests_total");
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "HTTP request latency in seconds",
+            ),
+            &["method", "route", "status"],
+        )
+        .expect("invalid metric http_request_duration_seconds");
+        let http_requests_in_flight = IntGauge::new(
+            "http_requests_in_flight",
+            "Number of HTTP requests being processed",
+        )
+        .expect("invalid metric http_requests_in_flight");
+        let db_pool_connections = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "Number of open database connections"),
+            &["pool"],
+        )
+        .expect("invalid metric db_pool_connections");
+        let db_pool_idle_connections = IntGaugeVec::new(
+            Opts::new(
+                "db_pool_idle_connections",
+                "Number of idle database connections",
+            ),
+            &["pool"],
+        )
+        .expect("invalid metric db_pool_idle_connections");
+        let db_pool_max_connections = IntGaugeVec::new(
+            Opts::new(
+                "db_pool_max_connections",
+                "Maximum number of database connections",
+            ),
+            &["pool"],
+        )
+        .expect("invalid metric db_pool_max_connections");
+        let db_transaction_begin_duration = Histogram::with_opts(HistogramOpts::new(
+            "db_transaction_begin_duration_seconds",
+            "Time spent beginning a transaction on TransactionLayer routes, including connection acquisition",
+        ))
+        .expect("invalid metric db_transaction_begin_duration_seconds");
+        let logins = IntCounterVec::new(
+            Opts::new("login_attempts_total", "Total number of login attempts"),
+            &["result"],
+        )
+        .expect("invalid metric login_attempts_total");
+        let slow_requests = IntCounterVec::new(
+            Opts::new(
+                "http_slow_requests_total",
+                "Total number of HTTP requests slower than the threshold",
+            ),
+            &["method", "route"],
+        )
+        .expect("invalid metric http_slow_requests_total");
+        let slow_queries = IntCounterVec::new(
+            Opts::new(
+                "db_slow_queries_total",
+                "Total number of SQL statements slower than the threshold",
+            ),
+            &["operation"],
+        )
+        .expect("invalid metric db_slow_queries_total");
+        let collectors: [Box<dyn prometheus::core::Collector>; 10] = [
+            Box::new(http_requests.clone()),
+            Box::new(http_request_duration.clone()),
+            Box::new(http_requests_in_flight.clone()),
+            Box::new(db_pool_connections.clone()),
+            Box::new(db_pool_idle_connections.clone()),
+            Box::new(db_pool_max_connections.clone()),
+            Box::new(db_transaction_begin_duration.clone()),
+            Box::new(logins.clone()),
+            Box::new(slow_requests.clone()),
+            Box::new(slow_queries.clone()),
+        ];
+        for collector in collectors {
+            registry
+                .register(collector)
+                .expect("metric is registered twice");
+        }
+        #[cfg(target_os = "linux")]
+        registry
+            .register(Box::new(
+                prometheus::process_collector::ProcessCollector::for_self(),
+            ))
+            .expect("process metrics are registered twice");
+        Self {
+            registry,
+            http_requests,
+            http_request_duration,
+            http_requests_in_flight,
+            db_pool_connections,
+            db_pool_idle_connections,
+            db_pool_max_connections,
+            db_transaction_begin_duration,
+            logins,
+            slow_requests,
+            slow_queries,
+        }
+    }
+}
+
+pub fn create_router() -> Router<AppState> {
+    Router::new().route(crate::config::get().metrics().path(), get(metrics))
+}
+
+/// Prometheus 文本格式的指标，连接池指标在抓取时采集
+async fn metrics(
+    State(AppState { db, .. }): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    authorize(&headers, crate::config::current().metrics().token())?;
+    for (name, conn) in db.connections() {
+        let pool = conn.get_postgres_connection_pool();
+        let labels = [name.as_str()];
+        METRICS
+            .db_pool_connections
+            .with_label_values(&labels)
+            .set(pool.size() as i64);
+        METRICS
+            .db_pool_idle_connections
+            .with_label_values(&labels)
+            .set(pool.num_idle() as i64);
+        METRICS
+            .db_pool_max_connections
+            .with_label_values(&labels)
+            .set(pool.options().get_max_connections() as i64);
+    }
+    let encoder = TextEncoder::new();
+    let mut buffer = Vec::new();
+    encoder
+        .encode(&METRICS.registry.gather(), &mut buffer)
+        .map_err(anyhow::Error::from)?;
+    Ok(([(header::CONTENT_TYPE, encoder.format_type())], buffer).into_response())
+}
+
+/// 配置了 `metrics.token` 时要求请求头 `Authorization: Bearer <token>`
+fn authorize(headers: &HeaderMap, token: Option<&str>) -> Result<(), ApiError> {
+    let Some(token) = token else {
+        return Ok(());
+    };
+    let provided = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    if constant_time_eq(provided.as_bytes(), token.as_bytes()) {
+        Ok(())
+    } else {
+        Err(ApiError::Unauthenticated(String::from(
+            "metrics token is missing or invalid",
+        )))
+    }
+}
+
+/// 统计请求数、延迟和处理中的请求数，路由标签使用匹配到的路由模板（例如 `/api/users/{id}`）
+pub async fn track(request: Request, next: Next) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| String::from(UNMATCHED_ROUTE));
+    let method = method_label(request.method());
+    let _in_flight = InFlight::start();
+    let start = Instant::now();
+    let response = next.run(request).await;
+    let status = response.status().as_u16().to_string();
+    let labels = [method, route.as_str(), status.as_str()];
+    METRICS.http_requests.with_label_values(&labels).inc();
+    METRICS
+        .http_request_duration
+        .with_label_values(&labels)
+        .observe(start.elapsed().as_secs_f64());
+    response
+}
+
+/// 非标准方法统一记为 OTHER
+fn method_label(method: &Method) -> &'static str {
+    const METHODS: [Method; 9] = [
+        Method::GET,
+        Method::POST,
+        Method::PUT,
+        Method::PATCH,
+        Method::DELETE,
+        Method::HEAD,
+        Method::OPTIONS,
+        Method::CONNECT,
+        Method::TRACE,
+    ];
+    METHODS
+        .iter()
+        .find(|known| *known == method)
+        .map(|known| known.as_str())
+        .unwrap_or("OTHER")
+}
+
+/// 请求结束或被取消（例如超时）时减少处理中的请求数
+struct InFlight;
+
+impl InFlight {
+    fn start() -> Self {
+        METRICS.http_requests_in_flight.inc();
+        Self
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        METRICS.http_requests_in_flight.dec();
+    }
+}
+
+/// 记录一次登录结果
+pub fn record_login(success: bool) {
+    let result = if success { "success" } else { "failure" };
+    METRICS.logins.with_label_values(&[result]).inc();
+}
+
+/// 记录开启请求级事务的耗时，包括从连接池获取连接和执行 BEGIN
+///
+/// 不在事务中的查询直接使用连接池，不计入该指标
+pub fn observe_db_transaction_begin(elapsed: Duration) {
+    METRICS
+        .db_transaction_begin_duration
+        .observe(elapsed.as_secs_f64());
+}
+
+/// 记录一次慢请求，route 为匹配到的路由模板
+pub fn record_slow_request(method: &Method, route: Option<&str>) {
+    METRICS
+        .slow_requests
+        .with_label_values(&[method_label(method), route.unwrap_or(UNMATCHED_ROUTE)])
+        .inc();
+}
+
+/// 记录一条慢 SQL，按语句类型（SELECT / INSERT 等）统计
+pub fn record_slow_query(operation: &str) {
+    METRICS.slow_queries.with_label_values(&[operation]).inc();
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn non_standard_methods_are_other() {
+        assert_eq!(method_label(&Method::GET), "GET");
+        assert_eq!(method_label(&Method::OPTIONS), "OPTIONS");
+        assert_eq!(
+            method_label(&Method::from_bytes(b"PROPFIND").unwrap()),
+            "OTHER"
+        );
+    }
+
+    #[test]
+    fn token_is_optional() {
+        assert!(authorize(&HeaderMap::new(), None).is_ok());
+    }
+
+    #[test]
+    fn token_must_match() {
+        let mut headers = HeaderMap::new();
+        assert!(authorize(&headers, Some("s3cret")).is_err());
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer wrong"),
+        );
+        assert!(authorize(&headers, Some("s3cret")).is_err());
+        headers.insert(header::AUTHORIZATION, HeaderValue::from_static("s3cret"));
+        assert!(authorize(&headers, Some("s3cret")).is_err());
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer s3cret"),
+        );
+        assert!(authorize(&headers, Some("s3cret")).is_ok());
+    }
+}
@@ -6,16 +6,11 @@ use axum::{
     http::header,
     response::{IntoResponse, Response},
 };
-use jsonwebtoken::jwk::Jwk;
-use tower_http::{
-    auth::{AsyncAuthorizeRequest, AsyncRequireAuthorizationLayer},
-    limit::ResponseBody,
-};
+use tower_http::auth::{AsyncAuthorizeRequest, AsyncRequireAuthorizationLayer};
 
 use crate::app::{
-    ApiError, AppResponse,
-    auth::{JWT, get_jwt},
-    json::Json,
+    ApiError,
+    auth::{JWT, Principal, get_jwt},
 };
 static AUTH_LAYER: LazyLock<AsyncRequireAuthorizationLayer<JWTAuth>> =
     LazyLock::new(|| AsyncRequireAuthorizationLayer::new(JWTAuth { jwt: get_jwt() }));
@@ -25,6 +20,7 @@ pub struct JWTAuth {
 }
 
 impl JWTAuth {
+    #[allow(dead_code)]
     pub fn new(jwt: &'static JWT) -> Self {
         Self { jwt }
     }
@@ -45,6 +41,10 @@ impl AsyncAuthorizeRequest<Body> for JWTAuth {
     fn authorize(&mut self, mut request: axum::http::Request<Body>) -> Self::Future {
         let jwt = self.jwt;
         Box::pin(async move {
+            // 按用户限流时已经解析过 token
+            if request.extensions().get::<Principal>().is_some() {
+                return Ok(request);
+            }
             let token = request
                 .headers()
                 .get(header::AUTHORIZATION)
@@ -66,9 +66,7 @@ impl AsyncAuthorizeRequest<Body> for JWTAuth {
                 })
                 .transpose()?
                 .ok_or_else(|| ApiError::Unauthenticated(String::from("")))??;
-            let principal = jwt
-                .decode(token)
-                .map_err(|err| ApiError::InternalServerError(err))?;
+            let principal = jwt.decode(token).map_err(ApiError::InternalServerError)?;
             request.extensions_mut().insert(principal);
             Ok(request)
         })
@@ -80,6 +78,16 @@ impl From<ApiError> for Response {
         error.into_response()
     }
 }
+/// 读取 Authorization 请求头中的 token
+pub(crate) fn bearer_token<B>(request: &axum::http::Request<B>) -> Option<&str> {
+    request
+        .headers()
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bear ")
+}
+
 pub fn get_auth_layer() -> &'static AsyncRequireAuthorizationLayer<JWTAuth> {
     &AUTH_LAYER
 }
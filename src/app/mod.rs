@@ -1,27 +1,49 @@
 pub mod auth;
+mod body_log;
+mod client_ip;
 mod common;
 mod database;
 mod enumeration;
 mod error;
+mod health;
+mod http_client;
 mod json;
 mod latency;
-mod logger;
+pub mod logger;
+pub mod metrics;
 mod middleware;
+mod multipart;
 mod path;
 mod query;
+mod rate_limit;
+mod redact;
+mod request_id;
 mod response;
 mod serde;
 mod server;
+mod shutdown;
+mod slow_request;
+mod telemetry;
+mod timeout;
+mod tls;
+mod transaction;
 mod valid;
 mod validation;
 
+pub use client_ip::ClientIp;
 pub use enumeration::Gender;
 pub use error::ApiError;
+pub use http_client::HttpClient;
+pub use request_id::RequestId;
 pub use response::AppResponse;
+pub use shutdown::on_shutdown;
+pub use transaction::{TransactionLayer, Tx, WithTx};
 
 pub use common::BasePageDTO;
 pub use common::PageInfoData;
+pub use database::Db;
 pub use error::ResponseErrorCode;
+pub use multipart::Multipart;
 pub use path::Path;
 pub use valid::ValidJson;
 pub use valid::ValidQuery;
@@ -29,10 +51,12 @@ pub use validation::is_mobile_phone;
 
 pub use middleware::get_auth_layer;
 
+use std::sync::Arc;
+
 use axum::Router;
 use sea_orm::DatabaseConnection;
 
-use crate::app::server::Server;
+use crate::app::{health::Readiness, rate_limit::MemoryStore, server::Server};
 
 pub(crate) type ApiResult<T> = Result<T, ApiError>;
 
@@ -40,22 +64,61 @@ pub(crate) type AppResult<T> = ApiResult<AppResponse<T>>;
 
 #[derive(Clone)]
 pub struct AppState {
-    pub db: DatabaseConnection,
+    pub db: Db,
+    pub readiness: Readiness,
+    pub http_client: HttpClient,
 }
 
 impl AppState {
-    pub fn new(db: DatabaseConnection) -> Self {
-        Self { db }
+    pub fn new(db: Db, readiness: Readiness) -> Self {
+        Self {
+            db,
+            readiness,
+            http_client: HttpClient::default(),
+        }
     }
 }
 pub async fn run(router: Router<AppState>) -> anyhow::Result<()> {
-    logger::init();
+    init()?;
     tracing::info!("Starting server...");
-    // init id generator
-    crate::utils::id::init()?;
-    let db = database::init().await?;
-    let state = AppState::new(db);
+    // 最先注册，最后执行：写完其他子系统停机过程中产生的日志和 span
+    on_shutdown("logging", logger::flush);
+    on_shutdown("telemetry", telemetry::shutdown);
+    let readiness = Readiness::default();
+    let db = database::init_for_server(readiness.clone()).await?;
+    let db = database::init_replicas(db).await?;
+    if let Some(check) = db.replica_check() {
+        readiness.register(check);
+    }
+    crate::config::on_reload(|config| {
+        if let Some(expiration) = config.auth().jwt_expiration() {
+            auth::get_jwt().set_expiration(expiration);
+        }
+        database::set_slow_query(config.database().slow_query());
+    });
+    crate::config::spawn_watcher()?;
+    // 停机回调按注册的逆序执行：数据库在日志和链路追踪之前关闭，之后注册的回调先执行，仍可以访问数据库
+    on_shutdown("database", {
+        let db = db.clone();
+        move || async move { db.close().await }
+    });
+    let state = AppState::new(db, readiness);
     let server_config = crate::config::get().server();
-    let server = Server::new(server_config);
-    server.start(state, router).await
+    let server = Server::new(server_config, Arc::new(MemoryStore::default()));
+    server.start(state, router).await?;
+    shutdown::run_hooks().await;
+    tracing::info!("Shutdown complete");
+    Ok(())
+}
+
+/// 初始化日志、id 生成器并连接数据库，供命令行子命令使用
+pub async fn bootstrap() -> anyhow::Result<DatabaseConnection> {
+    init()?;
+    database::init().await
+}
+
+fn init() -> anyhow::Result<()> {
+    logger::init()?;
+    // init id generator
+    crate::utils::id::init()
 }
@@ -0,0 +1,424 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{Request, State},
+    http::{HeaderMap, HeaderName, HeaderValue, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use futures::future::BoxFuture;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    app::{
+        ApiError,
+        auth::{Principal, get_jwt},
+        client_ip::client_ip,
+        middleware::bearer_token,
+    },
+    config::rate_limit::{Quota, RateLimitConfig, RateLimitKey},
+};
+
+/// 内存存储清理过期 key 的间隔
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 内存存储默认最多保存的 key 数量
+const DEFAULT_CAPACITY: usize = 100_000;
+
+/// 限流判定结果
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimitDecision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    /// 配额完全恢复所需时间
+    pub reset: Duration,
+    /// 被拒绝时距离下一次允许请求的时间
+    pub retry_after: Duration,
+}
+
+/// 限流状态存储，多实例部署时可以实现共享存储（例如 Redis）
+pub trait RateLimitStore: Send + Sync {
+    /// 对 key 消耗一次配额
+    fn check<'a>(
+        &'a self,
+        key: &'a str,
+        quota: &'a Quota,
+    ) -> BoxFuture<'a, anyhow::Result<RateLimitDecision>>;
+}
+
+/// GCRA 算法：`tat` 为理论到达时间，返回判定结果和允许时新的 tat（均为纳秒）
+pub fn gcra(now: u64, tat: u64, quota: &Quota) -> (RateLimitDecision, Option<u64>) {
+    let interval = (quota.period.as_nanos() as u64 / quota.limit as u64).max(1);
+    let tolerance = interval.saturating_mul(quota.burst as u64);
+    let tat = tat.max(now);
+    let new_tat = tat.saturating_add(interval);
+    let allow_at = new_tat.saturating_sub(tolerance);
+    if now < allow_at {
+        let decision = RateLimitDecision {
+            allowed: false,
+            limit: quota.limit,
+            remaining: 0,
+            reset: Duration::from_nanos(tat - now),
+            retry_after: Duration::from_nanos(allow_at - now),
+        };
+        return (decision, None);
+    }
+    let decision = RateLimitDecision {
+        allowed: true,
+        limit: quota.limit,
+        remaining: (now.saturating_add(tolerance).saturating_sub(new_tat) / interval) as u32,
+        reset: Duration::from_nanos(new_tat - now),
+        retry_after: Duration::ZERO,
+    };
+    (decision, Some(new_tat))
+}
+
+/// 单实例内存存储，key 数量达到上限时先清理已恢复的 key，仍然满时淘汰最早恢复的 key
+pub struct MemoryStore {
+    started: Instant,
+    capacity: usize,
+    state: Mutex<MemoryState>,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    buckets: HashMap<String, u64>,
+    last_cleanup: u64,
+}
+
+impl MemoryStore {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            started: Instant::now(),
+            capacity: capacity.max(1),
+            state: Mutex::default(),
+        }
+    }
+}
+
+impl Default for MemoryStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl MemoryState {
+    /// 为新 key 腾出空间
+    fn make_room(&mut self, now: u64, capacity: usize) {
+        if self.buckets.len() < capacity {
+            return;
+        }
+        self.buckets.retain(|_, tat| *tat > now);
+        self.last_cleanup = now;
+        if self.buckets.len() < capacity {
+            return;
+        }
+        let earliest = self
+            .buckets
+            .iter()
+            .min_by_key(|(_, tat)| **tat)
+            .map(|(key, _)| key.clone());
+        if let Some(key) = earliest {
+            self.buckets.remove(&key);
+        }
+    }
+}
+
+impl RateLimitStore for MemoryStore {
+    fn check<'a>(
+        &'a self,
+        key: &'a str,
+        quota: &'a Quota,
+    ) -> BoxFuture<'a, anyhow::Result<RateLimitDecision>> {
+        let now = self.started.elapsed().as_nanos() as u64;
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if now - state.last_cleanup >= CLEANUP_INTERVAL.as_nanos() as u64 {
+            // tat 早于当前时间的 key 配额已完全恢复，可以删除
+            state.buckets.retain(|_, tat| *tat > now);
+            state.last_cleanup = now;
+        }
+        let tat = state.buckets.get(key).copied().unwrap_or(now);
+        let (decision, new_tat) = gcra(now, tat, quota);
+        if let Some(new_tat) = new_tat {
+            if !state.buckets.contains_key(key) {
+                state.make_room(now, self.capacity);
+            }
+            state.buckets.insert(key.to_string(), new_tat);
+        }
+        Box::pin(std::future::ready(Ok(decision)))
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimiter {
+    store: Arc<dyn RateLimitStore>,
+}
+
+impl RateLimiter {
+    pub fn new(store: Arc<dyn RateLimitStore>) -> Self {
+        Self { store }
+    }
+}
+
+/// 按 `server.middleware.rate_limit` 配置限流，修改后热加载生效
+///
+/// 响应中携带 `RateLimit-Limit` / `RateLimit-Remaining` / `RateLimit-Reset`，
+/// 超出配额时返回 429 和 `Retry-After`；存储不可用时放行请求
+pub async fn rate_limit(
+    State(limiter): State<RateLimiter>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let config = crate::config::current();
+    let rate_limit = config.server().middleware().rate_limit();
+    if !rate_limit.enabled() {
+        return next.run(request).await;
+    }
+    let Some(quota) = rate_limit.quota_for(request.method(), request.uri().path()) else {
+        return next.run(request).await;
+    };
+    let client = client_key(&mut request, quota.key, rate_limit);
+    let key = format!("{}:{}", quota.name, client);
+    let decision = match limiter.store.check(&key, &quota).await {
+        Ok(decision) => decision,
+        Err(err) => {
+            tracing::warn!("Rate limit store is unavailable: {:#}", err);
+            return next.run(request).await;
+        }
+    };
+    let mut response = if decision.allowed {
+        next.run(request).await
+    } else {
+        tracing::warn!(
+            "Rate limit exceeded: quota={}, client={}",
+            quota.name,
+            fingerprint(&client)
+        );
+        ApiError::TooManyRequests.into_response()
+    };
+    set_headers(response.headers_mut(), &decision);
+    response
+}
+
+/// 限流对象标识，取不到用户或已知的 API key 时按 IP 限流；API key 只保存摘要
+fn client_key(request: &mut Request, key: RateLimitKey, config: &RateLimitConfig) -> String {
+    match key {
+        RateLimitKey::User => {
+            if let Some(id) = principal_id(request) {
+                return format!("user:{id}");
+            }
+        }
+        RateLimitKey::ApiKey => {
+            if let Some(api_key) = request
+                .headers()
+                .get(config.api_key_header())
+                .and_then(|value| value.to_str().ok())
+                .filter(|api_key| config.is_known_api_key(api_key))
+            {
+                return format!("api_key:{}", fingerprint(api_key));
+            }
+        }
+        RateLimitKey::Ip => {}
+    }
+    let ip = client_ip(request.headers(), request.extensions())
+        .map(|ip| ip.to_string())
+        .unwrap_or_default();
+    format!("ip:{ip}")
+}
+
+/// SHA-256 前 8 字节的十六进制，日志和存储中代替原始的 API key / 用户 id
+fn fingerprint(value: &str) -> String {
+    Sha256::digest(value.as_bytes())
+        .iter()
+        .take(8)
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// 优先使用 extensions 中已解析的用户；限流在认证之前执行时解析一次并写入 extensions，认证中间件直接复用
+fn principal_id(request: &mut Request) -> Option<String> {
+    if let Some(principal) = request.extensions().get::<Principal>() {
+        return Some(principal.id.clone());
+    }
+    let principal = get_jwt().decode(bearer_token(request)?).ok()?;
+    let id = principal.id.clone();
+    request.extensions_mut().insert(principal);
+    Some(id)
+}
+
+fn set_headers(headers: &mut HeaderMap, decision: &RateLimitDecision) {
+    const LIMIT: HeaderName = HeaderName::from_static("ratelimit-limit");
+    const REMAINING: HeaderName = HeaderName::from_static("ratelimit-remaining");
+    const RESET: HeaderName = HeaderName::from_static("ratelimit-reset");
+    headers.insert(LIMIT, HeaderValue::from(decision.limit));
+    headers.insert(REMAINING, HeaderValue::from(decision.remaining));
+    headers.insert(RESET, HeaderValue::from(ceil_secs(decision.reset)));
+    if !decision.allowed {
+        headers.insert(
+            header::RETRY_AFTER,
+            HeaderValue::from(ceil_secs(decision.retry_after)),
+        );
+    }
+}
+
+fn ceil_secs(duration: Duration) -> u64 {
+    duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: u64 = 1_000_000_000;
+
+    /// 每秒 1 个请求，允许突发 3 个
+    fn quota() -> Quota {
+        Quota {
+            name: "test".to_string(),
+            key: RateLimitKey::Ip,
+            limit: 10,
+            period: Duration::from_secs(10),
+            burst: 3,
+        }
+    }
+
+    #[test]
+    fn burst_is_exhausted() {
+        let quota = quota();
+        let mut tat = 0;
+        for remaining in [2, 1, 0] {
+            let (decision, new_tat) = gcra(0, tat, &quota);
+            assert!(decision.allowed);
+            assert_eq!(decision.limit, 10);
+            assert_eq!(decision.remaining, remaining);
+            tat = new_tat.unwrap();
+        }
+        let (decision, new_tat) = gcra(0, tat, &quota);
+        assert!(!decision.allowed);
+        assert_eq!(decision.remaining, 0);
+        assert_eq!(new_tat, None);
+    }
+
+    #[test]
+    fn rejected_request_reports_retry_after() {
+        let quota = quota();
+        let (decision, _) = gcra(SECOND / 2, 3 * SECOND, &quota);
+        assert!(!decision.allowed);
+        assert_eq!(decision.retry_after, Duration::from_millis(500));
+        assert_eq!(decision.reset, Duration::from_millis(2500));
+        assert_eq!(ceil_secs(decision.retry_after), 1);
+
+        let (decision, new_tat) = gcra(SECOND, 3 * SECOND, &quota);
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 0);
+        assert_eq!(new_tat, Some(4 * SECOND));
+    }
+
+    #[test]
+    fn quota_recovers_over_time() {
+        let quota = quota();
+        let (decision, new_tat) = gcra(10 * SECOND, 3 * SECOND, &quota);
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 2);
+        assert_eq!(new_tat, Some(11 * SECOND));
+    }
+
+    #[test]
+    fn large_tat_does_not_overflow() {
+        let quota = quota();
+        let (decision, _) = gcra(u64::MAX - 5 * SECOND, u64::MAX - SECOND, &quota);
+        assert!(!decision.allowed);
+        let (decision, _) = gcra(u64::MAX, u64::MAX - 5 * SECOND, &quota);
+        assert!(decision.allowed);
+    }
+
+    #[tokio::test]
+    async fn keys_are_isolated() {
+        let store = MemoryStore::default();
+        let quota = quota();
+        for _ in 0..3 {
+            assert!(store.check("a", &quota).await.unwrap().allowed);
+        }
+        assert!(!store.check("a", &quota).await.unwrap().allowed);
+        let decision = store.check("b", &quota).await.unwrap();
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 2);
+    }
+
+    #[tokio::test]
+    async fn full_store_evicts_earliest_key() {
+        let store = MemoryStore::new(2);
+        let quota = quota();
+        for key in ["a", "a", "b", "c"] {
+            assert!(store.check(key, &quota).await.unwrap().allowed);
+        }
+        let state = store.state.lock().unwrap();
+        assert_eq!(state.buckets.len(), 2);
+        assert!(state.buckets.contains_key("a"));
+        assert!(!state.buckets.contains_key("b"));
+    }
+
+    /// 记录收到的 key，每个 key 只允许一次请求
+    #[derive(Default)]
+    struct RecordingStore {
+        keys: Mutex<Vec<String>>,
+    }
+
+    impl RateLimitStore for RecordingStore {
+        fn check<'a>(
+            &'a self,
+            key: &'a str,
+            _quota: &'a Quota,
+        ) -> BoxFuture<'a, anyhow::Result<RateLimitDecision>> {
+            let mut keys = self.keys.lock().unwrap();
+            let allowed = !keys.iter().any(|seen| seen == key);
+            keys.push(key.to_string());
+            Box::pin(std::future::ready(Ok(RateLimitDecision {
+                allowed,
+                limit: 1,
+                remaining: 0,
+                reset: Duration::from_secs(60),
+                retry_after: Duration::from_secs(60),
+            })))
+        }
+    }
+
+    #[tokio::test]
+    async fn middleware_uses_injected_store() {
+        use axum::{Router, body::Body, http::StatusCode, routing::get};
+        use tower::ServiceExt;
+
+        crate::config::init_for_test();
+        let store = Arc::new(RecordingStore::default());
+        let app = Router::new().route("/limited", get(|| async {})).layer(
+            axum::middleware::from_fn_with_state(RateLimiter::new(store.clone()), rate_limit),
+        );
+        let send = |api_key: &'static str| {
+            let request = Request::get("/limited")
+                .header("x-api-key", api_key)
+                .body(Body::empty())
+                .unwrap();
+            app.clone().oneshot(request)
+        };
+
+        assert_eq!(send("known-key").await.unwrap().status(), StatusCode::OK);
+        let response = send("known-key").await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "60");
+        // 未知的 key 按 IP 限流，换 key 不能绕过
+        assert_eq!(send("forged-1").await.unwrap().status(), StatusCode::OK);
+        assert_eq!(
+            send("forged-2").await.unwrap().status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+
+        let keys = store.keys.lock().unwrap();
+        let api_key = format!("/limited:api_key:{}", fingerprint("known-key"));
+        assert_eq!(*keys, [&api_key, &api_key, "/limited:ip:", "/limited:ip:"]);
+    }
+}
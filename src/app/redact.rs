@@ -0,0 +1,310 @@
+use std::{
+    borrow::Cow,
+    io::{self, Write},
+    sync::{Arc, LazyLock},
+};
+
+use arc_swap::ArcSwapOption;
+use regex::{Captures, Regex};
+use tracing_subscriber::fmt::MakeWriter;
+
+use crate::config::logging::RedactConfig;
+
+pub const MASK: &str = "***";
+/// 控制台彩色输出时字段名和值之间的 ANSI 转义序列
+const ANSI: &str = r"(?:\x1b\[[0-9;]*m)*";
+
+/// `Authorization` 请求头中 `Bearer xxx` / `Bear xxx` / `Basic xxx` 形式的凭证
+static CREDENTIAL_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?i)(\bauthorization(?:\\?["'])?\s*[:=]\s*(?:\\?["'])?(?:Bearer|Bear|Basic)\s+)[A-Za-z0-9._~+/=-]+"#,
+    )
+    .unwrap_or_else(|e| panic!("Failed to compile credential regex: {}", e))
+});
+/// JWT
+static JWT_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\beyJ[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+\.[A-Za-z0-9_-]*")
+        .unwrap_or_else(|e| panic!("Failed to compile jwt regex: {}", e))
+});
+/// 连续的数字，只有恰好 11 位且符合手机号号段时才脱敏，避免误伤更长的数字（例如 ID）中的片段
+static MOBILE_PHONE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\d{11,}").unwrap_or_else(|e| panic!("Failed to compile mobile phone regex: {}", e))
+});
+
+static REDACTOR: ArcSwapOption<Redactor> = ArcSwapOption::const_empty();
+
+struct Redactor {
+    /// 匹配 `key=value`、`"key":value`、`key: "value"` 和彩色输出的 `key: value`（包括 JSON 日志中转义后的引号），
+    /// 不匹配 `key: value` 形式的普通文本，例如校验错误 `password: length`
+    fields: Option<Regex>,
+    /// 小写的字段名，用于按名称脱敏结构化字段（例如导出的 span 属性）
+    names: Vec<String>,
+    mask_mobile_phone: bool,
+}
+
+impl Redactor {
+    fn new(config: &RedactConfig) -> anyhow::Result<Self> {
+        let mut fields = config.fields();
+        // 较长的字段名优先匹配，例如 access_token 优先于 token
+        fields.sort_by_key(|field| std::cmp::Reverse(field.len()));
+        let fields = (!fields.is_empty())
+            .then(|| {
+                let names = fields
+                    .iter()
+                    .map(|field| regex::escape(field.trim()))
+                    .collect::<Vec<_>>()
+                    .join("|");
+                let quoted = r#"\\"[^"\\]*\\"|"[^"]*""#;
+                Regex::new(&format!(
+                    r#"(?i)((?:(?:\b|\x1b\[[0-9;]*m)(?:{names})\b{ANSI}\s*={ANSI}|\\?"(?:{names})\\?"\s*:|\x1b\[[0-9;]*m(?:{names})\b{ANSI}:{ANSI})\s*)({quoted}|(?:(?:Bearer|Bear|Basic)\s+)?[^\s,;&)}}\]"\x1b]+)|(\b(?:{names})\b\s*:\s*)({quoted})"#
+                ))
+            })
+            .transpose()?;
+        Ok(Self {
+            fields,
+            names: config
+                .fields()
+                .iter()
+                .map(|field| field.trim().to_lowercase())
+                .collect(),
+            mask_mobile_phone: config.mask_mobile_phone(),
+        })
+    }
+
+    fn redact<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut text = replace(Cow::Borrowed(text), &CREDENTIAL_REGEX, |caps: &Captures| {
+            format!("{}{MASK}", &caps[1])
+        });
+        if let Some(fields) = &self.fields {
+            text = replace(text, fields, |caps: &Captures| {
+                let (prefix, value) = match (caps.get(1), caps.get(2)) {
+                    (Some(prefix), Some(value)) => (prefix.as_str(), value.as_str()),
+                    _ => (&caps[3], &caps[4]),
+                };
+                let quote = if value.starts_with("\\\"") {
+                    "\\\""
+                } else if value.starts_with('"') {
+                    "\""
+                } else {
+                    ""
+                };
+                format!("{prefix}{quote}{MASK}{quote}")
+            });
+        }
+        text = replace(text, &JWT_REGEX, |_: &Captures| String::from(MASK));
+        if self.mask_mobile_phone {
+            text = replace(text, &MOBILE_PHONE_REGEX, |caps: &Captures| {
+                mask_mobile_phone(&caps[0])
+            });
+        }
+        text
+    }
+}
+
+/// 只保留前 3 位和后 4 位
+fn mask_mobile_phone(digits: &str) -> String {
+    let bytes = digits.as_bytes();
+    if bytes.len() == 11 && bytes[0] == b'1' && (b'3'..=b'9').contains(&bytes[1]) {
+        format!("{}****{}", &digits[..3], &digits[7..])
+    } else {
+        digits.to_string()
+    }
+}
+
+fn replace<'a>(
+    text: Cow<'a, str>,
+    regex: &Regex,
+    replacer: impl FnMut(&Captures) -> String,
+) -> Cow<'a, str> {
+    match regex.replace_all(&text, replacer) {
+        Cow::Borrowed(_) => text,
+        Cow::Owned(replaced) => Cow::Owned(replaced),
+    }
+}
+
+/// 按 `logging.redact` 配置初始化，配置热加载时重新生成
+pub fn init(config: &RedactConfig) -> anyhow::Result<()> {
+    let redactor = config
+        .enabled()
+        .then(|| Redactor::new(config))
+        .transpose()?;
+    REDACTOR.store(redactor.map(Arc::new));
+    Ok(())
+}
+
+/// 脱敏文本中的密码、token、手机号等敏感信息，未开启时原样返回
+pub fn redact(text: &str) -> Cow<'_, str> {
+    match REDACTOR.load().as_deref() {
+        Some(redactor) => redactor.redact(text),
+        None => Cow::Borrowed(text),
+    }
+}
+
+/// 字段名是否属于需要脱敏的字段（不区分大小写），未开启时返回 false
+pub fn is_sensitive_field(name: &str) -> bool {
+    REDACTOR.load().as_deref().is_some_and(|redactor| {
+        redactor
+            .names
+            .iter()
+            .any(|field| field.eq_ignore_ascii_case(name))
+    })
+}
+
+/// 写入日志前脱敏，fmt 层每条日志只调用一次 write
+pub struct RedactWriter<M>(pub M);
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for RedactWriter<M> {
+    type Writer = Redacting<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        Redacting(self.0.make_writer())
+    }
+}
+
+pub struct Redacting<W>(W);
+
+impl<W: Write> Write for Redacting<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match std::str::from_utf8(buf) {
+            Ok(text) => self.0.write_all(redact(text).as_bytes())?,
+            Err(_) => self.0.write_all(buf)?,
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redact(text: &str) -> String {
+        Redactor::new(&RedactConfig::default())
+            .unwrap()
+            .redact(text)
+            .into_owned()
+    }
+
+    fn config(yaml: &str) -> RedactConfig {
+        config::Config::builder()
+            .add_source(config::File::from_str(yaml, config::FileFormat::Yaml))
+            .build()
+            .and_then(|config| config.try_deserialize())
+            .unwrap()
+    }
+
+    #[test]
+    fn masks_authorization_credentials() {
+        let redactor = Redactor::new(&config("fields: []")).unwrap();
+        let redact = |text| redactor.redact(text).into_owned();
+        assert_eq!(
+            redact("authorization: Bear abc.def"),
+            "authorization: Bear ***"
+        );
+        assert_eq!(
+            redact(r#"headers={"Authorization": "Bearer abc123"}"#),
+            r#"headers={"Authorization": "Bearer ***"}"#
+        );
+        assert_eq!(
+            redact("authorization=Basic dXNlcjpwYXNz"),
+            "authorization=Basic ***"
+        );
+    }
+
+    #[test]
+    fn authorization_field_masks_whole_credential() {
+        assert_eq!(
+            redact("authorization: Bear abc.def"),
+            "authorization: Bear ***"
+        );
+        assert_eq!(redact("authorization=Bear abc.def"), "authorization=***");
+        assert_eq!(
+            redact(r#"{"authorization":"Bearer abc"}"#),
+            r#"{"authorization":"***"}"#
+        );
+    }
+
+    #[test]
+    fn keeps_credential_words_in_prose() {
+        let text = "Authorization header Bear is missing";
+        assert_eq!(redact(text), text);
+        let text = "unauthenticated: Basic auth is not supported";
+        assert_eq!(redact(text), text);
+    }
+
+    #[test]
+    fn masks_jwt() {
+        assert_eq!(
+            redact("token eyJhbGciOiJIUzI1NiJ9.eyJzdWIiOiIxIn0.sig-_1 issued"),
+            "token *** issued"
+        );
+    }
+
+    #[test]
+    fn masks_field_values() {
+        assert_eq!(redact("password=s3cret user=a"), "password=*** user=a");
+        assert_eq!(
+            redact(r#"{"password":"s3cret","account":"a"}"#),
+            r#"{"password":"***","account":"a"}"#
+        );
+        assert_eq!(
+            redact(r#"{\"accessToken\": \"abc\"}"#),
+            r#"{\"accessToken\": \"***\"}"#
+        );
+        assert_eq!(
+            redact(r#"LoginDTO { password: "s3cret" }"#),
+            r#"LoginDTO { password: "***" }"#
+        );
+        assert_eq!(
+            redact("\x1b[3mpassword\x1b[0m\x1b[2m=\x1b[0ms3cret"),
+            "\x1b[3mpassword\x1b[0m\x1b[2m=\x1b[0m***"
+        );
+        assert_eq!(
+            redact("\x1b[3mpassword\x1b[0m: s3cret"),
+            "\x1b[3mpassword\x1b[0m: ***"
+        );
+    }
+
+    #[test]
+    fn longer_field_names_take_precedence() {
+        assert_eq!(redact("access_token=abc"), "access_token=***");
+        assert_eq!(redact("x-api-key=abc"), "x-api-key=***");
+    }
+
+    #[test]
+    fn keeps_field_names_in_prose() {
+        let text = "password: length must be between 6 and 20";
+        assert_eq!(redact(text), text);
+        let text = "body param error: password: Validation error: length";
+        assert_eq!(redact(text), text);
+    }
+
+    #[test]
+    fn masks_mobile_phones() {
+        assert_eq!(redact("phone 13912345678"), "phone 139****5678");
+        assert_eq!(redact("13912345678,15812345678"), "139****5678,158****5678");
+        assert_eq!(redact("user13912345678"), "user139****5678");
+    }
+
+    #[test]
+    fn keeps_other_numbers() {
+        for text in [
+            "id 213912345678",
+            "id 139123456789",
+            "order 12345678901",
+            "elapsed 1391234567",
+        ] {
+            assert_eq!(redact(text), text);
+        }
+    }
+
+    #[test]
+    fn phone_masking_can_be_disabled() {
+        let config = config("mask_mobile_phone: false");
+        let redactor = Redactor::new(&config).unwrap();
+        assert_eq!(redactor.redact("phone 13912345678"), "phone 13912345678");
+    }
+}
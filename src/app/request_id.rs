@@ -0,0 +1,144 @@
+use std::fmt;
+
+use axum::{
+    extract::{FromRequestParts, Request},
+    http::{HeaderName, HeaderValue, request::Parts},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::app::ApiError;
+
+/// 请求头中的请求 ID 最大长度
+const MAX_LENGTH: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: RequestId;
+}
+
+/// 请求 ID，优先使用请求头中合法的 ID，否则生成新的
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    /// 当前请求的 ID，不在请求处理过程中时返回 None
+    pub fn current() -> Option<RequestId> {
+        REQUEST_ID.try_with(Clone::clone).ok()
+    }
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for RequestId {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts.extensions.get::<RequestId>().cloned().ok_or_else(|| {
+            ApiError::InternalServerError(anyhow::anyhow!(
+                "RequestId extractor requires the request_id middleware"
+            ))
+        })
+    }
+}
+
+/// 读取或生成请求 ID，写入请求扩展和响应头，错误响应体中也会带上该 ID
+pub async fn request_id(mut request: Request, next: Next) -> Response {
+    let header = crate::config::current()
+        .server()
+        .middleware()
+        .request_id()
+        .header();
+    let id = match request
+        .headers()
+        .get(&header)
+        .and_then(|value| value.to_str().ok())
+    {
+        Some(id) if is_valid(id) => id.to_string(),
+        Some(id) => {
+            tracing::debug!("Ignore invalid request id: {:?}", id);
+            xid::new().to_string()
+        }
+        None => xid::new().to_string(),
+    };
+    let request_id = RequestId(id);
+    request.extensions_mut().insert(request_id.clone());
+    let mut response = REQUEST_ID
+        .scope(request_id.clone(), next.run(request))
+        .await;
+    set_header(&mut response, header, &request_id);
+    response
+}
+
+fn set_header(response: &mut Response, header: HeaderName, request_id: &RequestId) {
+    if let Ok(value) = HeaderValue::from_str(request_id.as_str()) {
+        response.headers_mut().insert(header, value);
+    }
+}
+
+/// 只接受字母、数字和 `-_.:`，避免日志注入
+fn is_valid(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_LENGTH
+        && id
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || b"-_.:".contains(&byte))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{Router, body::Body, routing::get};
+    use tower::ServiceExt;
+
+    use super::*;
+
+    async fn send(id: HeaderValue) -> (Option<HeaderValue>, String) {
+        crate::config::init_for_test();
+        let app = Router::new()
+            .route("/", get(|| async { Err::<(), _>(ApiError::NotFound) }))
+            .layer(axum::middleware::from_fn(request_id));
+        let request = Request::get("/")
+            .header("x-request-id", id)
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let header = response.headers().get("x-request-id").cloned();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (header, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn valid_id_is_echoed() {
+        let (header, body) = send(HeaderValue::from_static("req-1_a.b:c")).await;
+        assert_eq!(header.unwrap(), "req-1_a.b:c");
+        assert!(body.contains(r#""requestId":"req-1_a.b:c""#), "{body}");
+    }
+
+    #[tokio::test]
+    async fn invalid_id_is_replaced() {
+        for id in [
+            HeaderValue::from_str(&"a".repeat(MAX_LENGTH + 1)).unwrap(),
+            HeaderValue::from_static("id with spaces"),
+            HeaderValue::from_static("id\"}"),
+            HeaderValue::from_bytes(b"id-\xff").unwrap(),
+        ] {
+            let (header, body) = send(id.clone()).await;
+            let header = header.unwrap();
+            assert_ne!(header, id);
+            let generated = header.to_str().unwrap();
+            assert!(is_valid(generated));
+            assert!(
+                body.contains(&format!(r#""requestId":"{generated}""#)),
+                "{body}"
+            );
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use axum::response::IntoResponse;
 use serde::{Deserialize, Serialize};
 
-use crate::app::error::ApiError;
+use crate::app::{error::ApiError, redact, request_id::RequestId};
 
 const SUCESSS_CODE: i32 = 200;
 const SUCESSS_MESSAGE: &str = "success";
@@ -11,6 +11,9 @@ pub struct AppResponse<T> {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<T>,
     pub message: String,
+    /// 失败时返回请求 ID，便于排查问题
+    #[serde(rename = "requestId", skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
 impl<T> AppResponse<T> {
@@ -19,6 +22,7 @@ impl<T> AppResponse<T> {
             code,
             data,
             message,
+            request_id: None,
         }
     }
     pub fn ok(data: Option<T>) -> Self {
@@ -30,7 +34,16 @@ impl<T> AppResponse<T> {
     }
 
     pub fn fail<M: AsRef<str>>(code: i32, message: M) -> Self {
-        AppResponse::new(code, None, String::from(message.as_ref()))
+        AppResponse::new(code, None, String::from(message.as_ref())).with_request_id()
+    }
+
+    pub fn fail_with_data<M: AsRef<str>>(code: i32, message: M, data: T) -> Self {
+        AppResponse::new(code, Some(data), String::from(message.as_ref())).with_request_id()
+    }
+
+    fn with_request_id(mut self) -> Self {
+        self.request_id = RequestId::current().map(|id| id.0);
+        self
     }
 
     #[allow(dead_code)]
@@ -38,11 +51,18 @@ impl<T> AppResponse<T> {
         let status_code = code as i32;
         AppResponse::fail(status_code, message)
     }
+    /// 4xx 返回脱敏后的错误信息，5xx 只返回通用信息，详细错误只记录在日志中
     pub fn fail_enum(api_error: &ApiError) -> Self {
-        AppResponse::fail(
-            api_error.status_code().as_u16() as i32,
-            api_error.to_string(),
-        )
+        let status_code = api_error.status_code();
+        let message = if status_code.is_server_error() {
+            status_code
+                .canonical_reason()
+                .unwrap_or("Internal Server Error")
+                .to_string()
+        } else {
+            redact::redact(&api_error.to_string()).into_owned()
+        };
+        AppResponse::fail(status_code.as_u16() as i32, message)
     }
 }
 
@@ -51,3 +71,31 @@ impl<T: Serialize> IntoResponse for AppResponse<T> {
         axum::Json(self).into_response()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::logging::RedactConfig;
+
+    #[test]
+    fn client_error_message_is_redacted() {
+        redact::init(&RedactConfig::default()).unwrap();
+        let response =
+            AppResponse::<()>::fail_enum(&ApiError::Unauthenticated(String::from("token=abc")));
+        assert_eq!(response.code, 401);
+        assert_eq!(response.message, "unauthenticated:token=***");
+    }
+
+    #[test]
+    fn server_error_message_is_generic() {
+        let error = ApiError::InternalServerError(anyhow::anyhow!("connect to 10.0.0.1 failed"));
+        let response = AppResponse::<()>::fail_enum(&error);
+        assert_eq!(response.code, 500);
+        assert_eq!(response.message, "Internal Server Error");
+        let error = ApiError::ServiceUnavailable(String::from("database is down"));
+        assert_eq!(
+            AppResponse::<()>::fail_enum(&error).message,
+            "Service Unavailable"
+        );
+    }
+}
@@ -1,81 +1,326 @@
-use std::{net::SocketAddr, time::Duration};
+use std::{
+    fmt::Display,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
 
-use anyhow::Ok;
+use anyhow::Context;
 use axum::{
     Router,
-    extract::{DefaultBodyLimit, Request},
-    http::{StatusCode, method},
+    extract::{ConnectInfo, DefaultBodyLimit, Request},
+};
+use hyper::body::Incoming;
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo, TokioTimer},
+    server::{conn::auto, graceful::GracefulShutdown},
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpListener,
+};
+use tower::ServiceExt;
+use tower_http::{
+    cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer},
+    normalize_path::NormalizePathLayer,
+    trace::TraceLayer,
 };
-use bytesize::ByteSize;
-use tokio::net::TcpListener;
-use tower_http::{normalize_path::NormalizePathLayer, timeout::TimeoutLayer, trace::TraceLayer};
 
 use crate::{
-    app::{AppState, auth::Principal, latency::LatencyOnResponse},
-    config::server::ServerConfig,
+    app::{
+        AppState, RequestId, body_log,
+        client_ip::client_ip,
+        health,
+        latency::LatencyOnResponse,
+        metrics,
+        rate_limit::{self, RateLimitStore, RateLimiter},
+        request_id, shutdown, slow_request, telemetry, timeout, tls,
+    },
+    config::{middleware::CorsConfig, server::ServerConfig},
 };
 pub struct Server {
     config: &'static ServerConfig,
+    rate_limiter: RateLimiter,
 }
 
 impl Server {
-    pub fn new(config: &'static ServerConfig) -> Self {
-        Self { config }
+    /// `rate_limit_store` 为限流状态存储，多实例部署时传入共享存储
+    pub fn new(config: &'static ServerConfig, rate_limit_store: Arc<dyn RateLimitStore>) -> Self {
+        Self {
+            config,
+            rate_limiter: RateLimiter::new(rate_limit_store),
+        }
     }
     pub async fn start(&self, state: AppState, router: Router<AppState>) -> anyhow::Result<()> {
+        let readiness = state.readiness.clone();
         let router = self.build_router(state, router);
-        let port = self.config.port();
-        let address = format!("0.0.0.0:{port}");
-        let listener = TcpListener::bind(address.clone()).await?;
-        tracing::info!("Server listening on  http://{}", address);
-        axum::serve(
-            listener,
-            router.into_make_service_with_connect_info::<SocketAddr>(),
-        )
-        .await?;
+        let tls_acceptor = self
+            .config
+            .tls()
+            .map(|tls| tls::acceptor(tls, self.config.http2().enabled()))
+            .transpose()?;
+        let listener = Listener::bind(self.config).await?;
+        let scheme = if tls_acceptor.is_some() {
+            "https"
+        } else {
+            "http"
+        };
+        tracing::info!("Server listening on  {}://{}", scheme, listener);
+        let builder = Arc::new(self.connection_builder());
+        let graceful = GracefulShutdown::new();
+        let shutdown_delay = self.config.shutdown_delay();
+        let shutdown = async move {
+            shutdown::signal().await;
+            readiness.begin_shutdown();
+            tokio::time::sleep(shutdown_delay).await;
+        };
+        tokio::pin!(shutdown);
+        loop {
+            let (stream, remote_addr) = tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok(accepted) => accepted,
+                    Err(err) => {
+                        // 文件句柄耗尽等错误，稍后重试避免空转
+                        tracing::warn!("Failed to accept connection: {}", err);
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        continue;
+                    }
+                },
+                _ = &mut shutdown => break,
+            };
+            let router = router.clone();
+            let builder = builder.clone();
+            let tls_acceptor = tls_acceptor.clone();
+            let watcher = graceful.watcher();
+            let handshake_timeout = self.config.header_read_timeout();
+            tokio::spawn(async move {
+                let stream: Box<dyn Io> = match tls_acceptor {
+                    Some(acceptor) => {
+                        match tokio::time::timeout(handshake_timeout, acceptor.accept(stream)).await
+                        {
+                            Ok(Ok(stream)) => Box::new(stream),
+                            Ok(Err(err)) => {
+                                tracing::debug!(
+                                    "TLS handshake with {} failed: {}",
+                                    remote_addr,
+                                    err
+                                );
+                                return;
+                            }
+                            Err(_) => {
+                                tracing::debug!("TLS handshake with {} timed out", remote_addr);
+                                return;
+                            }
+                        }
+                    }
+                    None => stream,
+                };
+                let service = hyper::service::service_fn(move |mut request: Request<Incoming>| {
+                    request.extensions_mut().insert(ConnectInfo(remote_addr));
+                    router.clone().oneshot(request)
+                });
+                let connection =
+                    builder.serve_connection_with_upgrades(TokioIo::new(stream), service);
+                if let Err(err) = watcher.watch(connection).await {
+                    tracing::debug!("Connection with {} closed: {}", remote_addr, err);
+                }
+            });
+        }
+        drop(listener);
+        tracing::info!("Stopped accepting connections, draining in-flight requests");
+        let shutdown_timeout = self.config.shutdown_timeout();
+        tokio::select! {
+            _ = graceful.shutdown() => {}
+            _ = tokio::time::sleep(shutdown_timeout) => {
+                tracing::warn!(
+                    "In-flight requests did not finish within {:?}, closing remaining connections",
+                    shutdown_timeout
+                );
+            }
+        }
+        tracing::info!("Server stopped");
         Ok(())
     }
 
+    fn connection_builder(&self) -> auto::Builder<TokioExecutor> {
+        let mut builder = auto::Builder::new(TokioExecutor::new());
+        builder
+            .http1()
+            .timer(TokioTimer::new())
+            .keep_alive(self.config.keep_alive())
+            .header_read_timeout(self.config.header_read_timeout());
+        let http2 = self.config.http2();
+        builder
+            .http2()
+            .timer(TokioTimer::new())
+            .max_concurrent_streams(http2.max_concurrent_streams())
+            .keep_alive_interval(http2.keep_alive_interval())
+            .keep_alive_timeout(http2.keep_alive_timeout());
+        if !http2.enabled() {
+            builder = builder.http1_only();
+        }
+        builder
+    }
+
     fn build_router(&self, state: AppState, router: Router<AppState>) -> axum::Router {
-        let timeout_layer =
-            TimeoutLayer::with_status_code(StatusCode::REQUEST_TIMEOUT, Duration::from_secs(120));
-        let body_limit_layer = DefaultBodyLimit::max(ByteSize::mib(10).as_u64() as usize);
-        let cors_layer = tower_http::cors::CorsLayer::new()
-            .allow_origin(tower_http::cors::Any)
-            .allow_methods(vec![
-                method::Method::GET,
-                method::Method::POST,
-                method::Method::PUT,
-                method::Method::DELETE,
-            ])
-            .allow_headers(tower_http::cors::Any)
-            .allow_credentials(false)
-            .max_age(Duration::from_secs(3600 * 12));
-        // 末尾的 / 去掉
-        let normalize_layer = NormalizePathLayer::trim_trailing_slash();
+        let middleware = self.config.middleware();
+        let ready_layer =
+            axum::middleware::from_fn_with_state(state.clone(), health::require_ready);
+        let rate_limit_layer =
+            axum::middleware::from_fn_with_state(self.rate_limiter.clone(), rate_limit::rate_limit);
+        let mut router = axum::Router::new()
+            .merge(router)
+            .layer(ready_layer)
+            .layer(rate_limit_layer)
+            .merge(health::create_router());
+        let metrics_enabled = crate::config::get().metrics().enabled();
+        if metrics_enabled {
+            router = router.merge(metrics::create_router());
+        }
+        if middleware.timeout().enabled() {
+            router = router.layer(axum::middleware::from_fn(timeout::timeout));
+        }
+        router = router.layer(if middleware.body_limit().enabled() {
+            DefaultBodyLimit::max(middleware.body_limit().max_size().as_u64() as usize)
+        } else {
+            DefaultBodyLimit::disable()
+        });
+        if metrics_enabled {
+            router = router.layer(axum::middleware::from_fn(metrics::track));
+        }
+        // 在 trace 层内，日志输出在 api request span 中
+        router = router
+            .layer(axum::middleware::from_fn(slow_request::slow_request))
+            .layer(axum::middleware::from_fn(body_log::body_log));
+        if middleware.trace() {
+            let trace_layer = TraceLayer::new_for_http()
+                .make_span_with(|request: &Request| {
+                    let method = request.method();
+                    let path = request.uri().path();
+                    let request_id = request
+                        .extensions()
+                        .get::<RequestId>()
+                        .map(|id| id.to_string())
+                        .unwrap_or_default();
+                    let client_ip = client_ip(request.headers(), request.extensions())
+                        .map(|ip| ip.to_string())
+                        .unwrap_or_default();
+                    // if let Some(principal) = request.extensions().get::<Principal>() {
+                    //     tracing::info_span!("api request",trace_id = %request_id, method=%method, user_id=%principal.id, path= %path)
+                    // } else {
+
+                    // }
+                    let span = tracing::info_span!("api request",trace_id = %request_id, method=%method, path= %path, client_ip = %client_ip, otel.kind = "server");
+                    telemetry::set_parent_from_headers(&span, request.headers());
+                    span
+                })
+                .on_request(())
+                .on_failure(())
+                .on_response(LatencyOnResponse);
+            router = router.layer(trace_layer);
+        }
+        router = router.layer(axum::middleware::from_fn(request_id::request_id));
+        if middleware.cors().enabled() {
+            router = router.layer(cors_layer(middleware.cors()));
+        }
+        if middleware.normalize_path() {
+            // 末尾的 / 去掉
+            router = router.layer(NormalizePathLayer::trim_trailing_slash());
+        }
+        router.with_state(state)
+    }
+}
 
-        let trace_layer = TraceLayer::new_for_http()
-            .make_span_with(|request: &Request| {
-                let method = request.method();
-                let path = request.uri().path();
-                let xid = xid::new();
-                // if let Some(principal) = request.extensions().get::<Principal>() {
-                //     tracing::info_span!("api request",trace_id = %xid, method=%method, user_id=%principal.id, path= %path)
-                // } else {
+fn cors_layer(config: &CorsConfig) -> CorsLayer {
+    CorsLayer::new()
+        // 允许的来源从最新配置读取，修改后热加载生效
+        .allow_origin(AllowOrigin::predicate(|origin, _| {
+            crate::config::current()
+                .server()
+                .middleware()
+                .cors()
+                .allows_origin(origin)
+        }))
+        .allow_methods(match config.allowed_methods() {
+            Some(methods) => AllowMethods::list(methods),
+            None => AllowMethods::any(),
+        })
+        .allow_headers(match config.allowed_headers() {
+            Some(headers) => AllowHeaders::list(headers),
+            None => AllowHeaders::any(),
+        })
+        .expose_headers(config.exposed_headers())
+        .allow_credentials(config.allow_credentials())
+        .max_age(config.max_age())
+}
 
-                // }
-                tracing::info_span!("api request",trace_id = %xid, method=%method, path= %path)
-            })
-            .on_request(())
-            .on_failure(())
-            .on_response(LatencyOnResponse);
-        axum::Router::new()
-            .merge(router)
-            .layer(timeout_layer)
-            .layer(body_limit_layer)
-            .layer(trace_layer)
-            .layer(cors_layer)
-            .layer(normalize_layer)
-            .with_state(state)
+trait Io: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Io for T {}
+
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener, PathBuf),
+}
+
+impl Listener {
+    async fn bind(config: &ServerConfig) -> anyhow::Result<Self> {
+        #[cfg(unix)]
+        if let Some(path) = config.unix_socket() {
+            // 清理上次异常退出残留的 socket 文件
+            if path.exists() {
+                std::fs::remove_file(path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?;
+            }
+            let listener = tokio::net::UnixListener::bind(path)
+                .with_context(|| format!("Failed to bind {}", path.display()))?;
+            return Ok(Listener::Unix(listener, path.clone()));
+        }
+        let host = config.host().parse::<IpAddr>()?;
+        let address = SocketAddr::new(host, config.port());
+        let listener = TcpListener::bind(address)
+            .await
+            .with_context(|| format!("Failed to bind {address}"))?;
+        Ok(Listener::Tcp(listener))
+    }
+
+    async fn accept(&self) -> std::io::Result<(Box<dyn Io>, SocketAddr)> {
+        match self {
+            Listener::Tcp(listener) => {
+                let (stream, address) = listener.accept().await?;
+                Ok((Box::new(stream), address))
+            }
+            #[cfg(unix)]
+            Listener::Unix(listener, _) => {
+                let (stream, _) = listener.accept().await?;
+                // unix socket 没有对端 IP，使用未指定地址
+                let address = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0);
+                Ok((Box::new(stream), address))
+            }
+        }
+    }
+}
+
+impl Display for Listener {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Listener::Tcp(listener) => match listener.local_addr() {
+                Ok(address) => write!(f, "{address}"),
+                Err(_) => write!(f, "unknown"),
+            },
+            #[cfg(unix)]
+            Listener::Unix(_, path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Listener {
+    fn drop(&mut self) {
+        if let Listener::Unix(_, path) = self {
+            let _ = std::fs::remove_file(path);
+        }
     }
 }
@@ -0,0 +1,66 @@
+use std::{
+    future::Future,
+    sync::{Mutex, PoisonError},
+};
+
+use futures::future::BoxFuture;
+
+type ShutdownHook = Box<dyn FnOnce() -> BoxFuture<'static, anyhow::Result<()>> + Send>;
+
+static HOOKS: Mutex<Vec<(&'static str, ShutdownHook)>> = Mutex::new(Vec::new());
+
+/// 注册停机回调，HTTP 请求排空后按注册的相反顺序执行（后注册的先执行）
+///
+/// 后台任务、缓存等子系统在回调中停止任务、刷新数据并释放资源
+pub fn on_shutdown<F, Fut>(name: &'static str, hook: F)
+where
+    F: FnOnce() -> Fut + Send + 'static,
+    Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+{
+    HOOKS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push((name, Box::new(move || Box::pin(hook()))));
+}
+
+/// 依次执行所有停机回调，单个回调失败或超时不影响后续回调
+pub async fn run_hooks() {
+    let timeout = crate::config::get().server().shutdown_timeout();
+    let hooks = std::mem::take(&mut *HOOKS.lock().unwrap_or_else(PoisonError::into_inner));
+    for (name, hook) in hooks.into_iter().rev() {
+        match tokio::time::timeout(timeout, hook()).await {
+            Ok(Ok(())) => tracing::info!("Shutdown hook `{}` completed", name),
+            Ok(Err(err)) => tracing::error!("Shutdown hook `{}` failed: {:#}", name, err),
+            Err(_) => tracing::error!("Shutdown hook `{}` timed out after {:?}", name, timeout),
+        }
+    }
+}
+
+/// 等待 SIGINT（Ctrl+C）或 SIGTERM
+pub async fn signal() {
+    let ctrl_c = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for SIGINT: {}", err);
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{SignalKind, signal};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(err) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", err);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = ctrl_c => tracing::info!("Received SIGINT, shutting down"),
+        _ = terminate => tracing::info!("Received SIGTERM, shutting down"),
+    }
+}
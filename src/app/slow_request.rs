@@ -0,0 +1,47 @@
+use std::time::Instant;
+
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::app::{auth::get_jwt, metrics, middleware::bearer_token};
+
+/// 按 `server.middleware.slow_request` 配置记录超过阈值的请求，修改后热加载生效
+pub async fn slow_request(request: Request, next: Next) -> Response {
+    let threshold = {
+        let config = crate::config::current();
+        let slow_request = config.server().middleware().slow_request();
+        if !slow_request.enabled() {
+            return next.run(request).await;
+        }
+        slow_request.threshold_for(request.uri().path())
+    };
+    let method = request.method().clone();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string());
+    // 只在请求变慢时才解析 token
+    let token = bearer_token(&request).map(String::from);
+    let start = Instant::now();
+    let response = next.run(request).await;
+    let elapsed = start.elapsed();
+    if elapsed >= threshold {
+        let user_id = token
+            .and_then(|token| get_jwt().decode(&token).ok())
+            .map(|principal| principal.id)
+            .unwrap_or_default();
+        metrics::record_slow_request(&method, route.as_deref());
+        tracing::warn!(
+            elapsed_ms = elapsed.as_millis() as u64,
+            threshold_ms = threshold.as_millis() as u64,
+            route = route.as_deref().unwrap_or_default(),
+            user_id = %user_id,
+            status = response.status().as_u16(),
+            "slow request"
+        );
+    }
+    response
+}
@@ -0,0 +1,338 @@
+use std::{
+    borrow::Cow,
+    sync::OnceLock,
+    time::{Duration, SystemTime},
+};
+
+use axum::http::HeaderMap;
+use opentelemetry::{
+    Array, KeyValue, StringValue, Value, global,
+    trace::{Span as _, SpanKind, Status, TraceContextExt, Tracer as _, TracerProvider as _},
+};
+use opentelemetry_http::{HeaderExtractor, HeaderInjector};
+use opentelemetry_otlp::{Protocol, WithExportConfig, WithHttpConfig};
+use opentelemetry_sdk::{
+    Resource,
+    error::OTelSdkResult,
+    propagation::TraceContextPropagator,
+    trace::{BatchSpanProcessor, Sampler, SdkTracer, SdkTracerProvider, SpanData, SpanExporter},
+};
+use tracing::Subscriber;
+use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::{app::redact, config::telemetry::TelemetryConfig};
+
+const TRACER_NAME: &str = "axum-starter";
+
+static PROVIDER: OnceLock<SdkTracerProvider> = OnceLock::new();
+
+/// 按 `telemetry` 配置创建 OTLP 导出层，未开启时返回 None
+///
+/// 同时注册 W3C trace context 传播器，用于解析和传递 `traceparent` / `tracestate`
+pub fn layer<S>() -> anyhow::Result<Option<OpenTelemetryLayer<S, SdkTracer>>>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    let config = crate::config::get().telemetry();
+    if !config.enabled() {
+        return Ok(None);
+    }
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    let provider = provider(config)?;
+    let tracer = provider.tracer(TRACER_NAME);
+    global::set_tracer_provider(provider.clone());
+    let _ = PROVIDER.set(provider);
+    Ok(Some(tracing_opentelemetry::layer().with_tracer(tracer)))
+}
+
+fn provider(config: &TelemetryConfig) -> anyhow::Result<SdkTracerProvider> {
+    let headers = config
+        .headers()
+        .iter()
+        .map(|(name, value)| (name.clone(), value.expose().clone()))
+        .collect();
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_protocol(Protocol::HttpBinary)
+        .with_endpoint(config.endpoint())
+        .with_timeout(config.timeout())
+        .with_headers(headers)
+        .build()?;
+    Ok(SdkTracerProvider::builder()
+        .with_span_processor(BatchSpanProcessor::builder(RedactingExporter(exporter)).build())
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            config.sample_ratio(),
+        ))))
+        .with_resource(
+            Resource::builder()
+                .with_service_name(config.service_name().to_string())
+                .build(),
+        )
+        .build())
+}
+
+/// 导出前按 `logging.redact` 脱敏 span 名称、属性、状态和事件（日志消息作为事件名称导出），
+/// 属性名属于脱敏字段时整体替换
+#[derive(Debug)]
+struct RedactingExporter<E>(E);
+
+impl<E: SpanExporter> SpanExporter for RedactingExporter<E> {
+    fn export(&self, mut batch: Vec<SpanData>) -> impl Future<Output = OTelSdkResult> + Send {
+        for span in &mut batch {
+            redact_span(span);
+        }
+        self.0.export(batch)
+    }
+    fn shutdown_with_timeout(&mut self, timeout: Duration) -> OTelSdkResult {
+        self.0.shutdown_with_timeout(timeout)
+    }
+    fn shutdown(&mut self) -> OTelSdkResult {
+        self.0.shutdown()
+    }
+    fn force_flush(&mut self) -> OTelSdkResult {
+        self.0.force_flush()
+    }
+    fn set_resource(&mut self, resource: &Resource) {
+        self.0.set_resource(resource);
+    }
+}
+
+fn redact_span(span: &mut SpanData) {
+    redact_text(&mut span.name);
+    span.attributes.iter_mut().for_each(redact_attribute);
+    if let Status::Error { description } = &mut span.status {
+        redact_text(description);
+    }
+    for event in span.events.events.iter_mut() {
+        redact_text(&mut event.name);
+        event.attributes.iter_mut().for_each(redact_attribute);
+    }
+}
+
+fn redact_text(text: &mut Cow<'static, str>) {
+    if let Cow::Owned(redacted) = redact::redact(text) {
+        *text = Cow::Owned(redacted);
+    }
+}
+
+fn redact_attribute(attribute: &mut KeyValue) {
+    if redact::is_sensitive_field(attribute.key.as_str()) {
+        attribute.value = Value::from(redact::MASK);
+        return;
+    }
+    let redact_value = |value: &mut StringValue| {
+        if let Cow::Owned(redacted) = redact::redact(value.as_str()) {
+            *value = StringValue::from(redacted);
+        }
+    };
+    match &mut attribute.value {
+        Value::String(value) => redact_value(value),
+        Value::Array(Array::String(values)) => values.iter_mut().for_each(redact_value),
+        _ => {}
+    }
+}
+
+/// 导出剩余的 span 并关闭导出器
+pub async fn shutdown() -> anyhow::Result<()> {
+    let Some(provider) = PROVIDER.get() else {
+        return Ok(());
+    };
+    // 关闭时会阻塞等待后台导出线程
+    tokio::task::spawn_blocking(|| provider.shutdown()).await??;
+    Ok(())
+}
+
+fn enabled() -> bool {
+    PROVIDER.get().is_some()
+}
+
+/// 使用请求头中的 `traceparent` / `tracestate` 作为 span 的父级
+pub fn set_parent_from_headers(span: &tracing::Span, headers: &HeaderMap) {
+    if !enabled() {
+        return;
+    }
+    let parent =
+        global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(headers)));
+    if parent.span().span_context().is_valid() {
+        let _ = span.set_parent(parent);
+    }
+}
+
+/// 调用外部 HTTP 服务时写入当前 span 的 `traceparent` / `tracestate`，见 [`HttpClient`](crate::app::HttpClient)
+pub fn inject_headers(headers: &mut HeaderMap) {
+    let context = tracing::Span::current().context();
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut HeaderInjector(headers))
+    });
+}
+
+/// 记录一条 SQL 的执行，作为当前 span 的子 span
+pub fn record_query(info: &sea_orm::metric::Info<'_>) {
+    if !enabled() {
+        return;
+    }
+    let parent = tracing::Span::current().context();
+    if !parent.span().span_context().is_valid() {
+        return;
+    }
+    let end = SystemTime::now();
+    let start = end.checked_sub(info.elapsed).unwrap_or(end);
+    let tracer = global::tracer(TRACER_NAME);
+    let mut span = tracer
+        .span_builder(query_name(&info.statement.sql))
+        .with_kind(SpanKind::Client)
+        .with_start_time(start)
+        .with_attributes([
+            KeyValue::new("db.system.name", "postgresql"),
+            KeyValue::new("db.query.text", info.statement.sql.clone()),
+        ])
+        .start_with_context(&tracer, &parent);
+    if info.failed {
+        span.set_status(Status::error("query failed"));
+    }
+    span.end_with_timestamp(end);
+}
+
+/// 以 SQL 的第一个关键字作为 span 名称，例如 SELECT / INSERT
+pub(super) fn query_name(sql: &str) -> String {
+    sql.split_whitespace()
+        .next()
+        .map(str::to_uppercase)
+        .unwrap_or_else(|| String::from("QUERY"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        sync::mpsc,
+        time::Duration,
+    };
+
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+    use crate::config::logging::RedactConfig;
+
+    /// 接收一个 OTLP 请求，返回请求头（小写）和请求体
+    fn mock_collector() -> (String, mpsc::Receiver<(String, Vec<u8>)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}/v1/traces", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 4096];
+            let head_end = loop {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+                if let Some(pos) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break pos + 4;
+                }
+            };
+            let head = String::from_utf8_lossy(&request[..head_end]).to_lowercase();
+            let length = head
+                .lines()
+                .find_map(|line| line.strip_prefix("content-length:"))
+                .map(|value| value.trim().parse::<usize>().unwrap())
+                .unwrap_or(0);
+            while request.len() < head_end + length {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                .unwrap();
+            sender.send((head, request[head_end..].to_vec())).unwrap();
+        });
+        (endpoint, receiver)
+    }
+
+    fn telemetry_config(endpoint: &str) -> TelemetryConfig {
+        config::Config::builder()
+            .add_source(config::File::from_str(
+                &format!(
+                    "enabled: true\nendpoint: {endpoint}\nservice_name: telemetry-test\nheaders:\n  x-api-key: secret-token"
+                ),
+                config::FileFormat::Yaml,
+            ))
+            .build()
+            .and_then(|config| config.try_deserialize())
+            .unwrap()
+    }
+
+    #[test]
+    fn query_name_is_first_keyword() {
+        assert_eq!(query_name("select * from sys_user"), "SELECT");
+        assert_eq!(query_name("\n  INSERT INTO sys_user VALUES ($1)"), "INSERT");
+        assert_eq!(query_name("   "), "QUERY");
+    }
+
+    #[test]
+    fn exports_spans_to_otlp_endpoint() {
+        let (endpoint, receiver) = mock_collector();
+        let provider = provider(&telemetry_config(&endpoint)).unwrap();
+        provider.tracer(TRACER_NAME).start("test span").end();
+        provider.force_flush().unwrap();
+
+        let (head, body) = receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(head.starts_with("post /v1/traces "), "{head}");
+        assert!(
+            head.contains("content-type: application/x-protobuf"),
+            "{head}"
+        );
+        assert!(head.contains("x-api-key: secret-token"), "{head}");
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.contains("telemetry-test"));
+        assert!(body.contains("test span"));
+        provider.shutdown().unwrap();
+    }
+
+    #[test]
+    fn injects_current_span_context() {
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        let provider = SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer(TRACER_NAME)));
+        tracing::subscriber::with_default(subscriber, || {
+            let mut headers = HeaderMap::new();
+            inject_headers(&mut headers);
+            assert!(headers.get("traceparent").is_none());
+
+            let _guard = tracing::info_span!("outgoing").entered();
+            inject_headers(&mut headers);
+            let traceparent = headers.get("traceparent").unwrap().to_str().unwrap();
+            assert!(traceparent.starts_with("00-"), "{traceparent}");
+        });
+    }
+
+    #[test]
+    fn redacts_spans_before_export() {
+        redact::init(&RedactConfig::default()).unwrap();
+        let (endpoint, receiver) = mock_collector();
+        let provider = provider(&telemetry_config(&endpoint)).unwrap();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer(TRACER_NAME)));
+        tracing::subscriber::with_default(subscriber, || {
+            let _guard =
+                tracing::info_span!("login", password = "span-secret", account = "admin001")
+                    .entered();
+            tracing::info!(
+                token = "event-secret",
+                "called with authorization=Bearer message-secret"
+            );
+        });
+        provider.force_flush().unwrap();
+
+        let (_, body) = receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.contains("admin001"));
+        assert!(body.contains("authorization=***"));
+        for secret in ["span-secret", "event-secret", "message-secret"] {
+            assert!(!body.contains(secret), "{secret} is exported");
+        }
+        provider.shutdown().unwrap();
+    }
+}
@@ -0,0 +1,15 @@
+use axum::{extract::Request, middleware::Next, response::Response};
+
+use crate::app::ApiError;
+
+/// 按 `server.middleware.timeout` 配置限制请求处理时间，支持按路由分组设置，修改后热加载生效
+pub async fn timeout(request: Request, next: Next) -> Result<Response, ApiError> {
+    let duration = crate::config::current()
+        .server()
+        .middleware()
+        .timeout()
+        .duration_for(request.uri().path());
+    tokio::time::timeout(duration, next.run(request))
+        .await
+        .map_err(|_| ApiError::RequestTimeout)
+}
@@ -0,0 +1,90 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use arc_swap::ArcSwap;
+use rustls::{
+    crypto::ring,
+    pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject},
+    server::{ClientHello, ResolvesServerCert},
+    sign::CertifiedKey,
+};
+use tokio_rustls::TlsAcceptor;
+
+use crate::config::{RELOAD_DEBOUNCE, server::TlsConfig};
+
+/// 支持热替换的证书，重新加载失败时继续使用旧证书
+#[derive(Debug)]
+struct CertResolver {
+    config: &'static TlsConfig,
+    key: ArcSwap<CertifiedKey>,
+}
+
+impl CertResolver {
+    fn reload(&self) {
+        match load_certified_key(self.config) {
+            Ok(key) => {
+                self.key.store(Arc::new(key));
+                tracing::info!("TLS certificate reloaded");
+            }
+            Err(err) => tracing::error!("Failed to reload TLS certificate: {:#}", err),
+        }
+    }
+}
+
+impl ResolvesServerCert for CertResolver {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(self.key.load_full())
+    }
+}
+
+/// 创建 TLS acceptor，开启 HTTP/2 时通过 ALPN 协商 h2
+pub fn acceptor(config: &'static TlsConfig, http2: bool) -> anyhow::Result<TlsAcceptor> {
+    let resolver = Arc::new(CertResolver {
+        config,
+        key: ArcSwap::from_pointee(load_certified_key(config)?),
+    });
+    if config.watch() {
+        watch(resolver.clone())?;
+    }
+    // SIGHUP 或配置文件变化时也重新加载证书
+    crate::config::on_reload({
+        let resolver = resolver.clone();
+        move |_| resolver.reload()
+    });
+    let mut tls_config =
+        rustls::ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()?
+            .with_no_client_auth()
+            .with_cert_resolver(resolver);
+    tls_config.alpn_protocols = if http2 {
+        vec![b"h2".to_vec(), b"http/1.1".to_vec()]
+    } else {
+        vec![b"http/1.1".to_vec()]
+    };
+    Ok(TlsAcceptor::from(Arc::new(tls_config)))
+}
+
+fn load_certified_key(config: &TlsConfig) -> anyhow::Result<CertifiedKey> {
+    let cert_file = config.cert_file();
+    let certs = CertificateDer::pem_file_iter(cert_file)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .with_context(|| format!("Failed to read certificate {}", cert_file.display()))?;
+    anyhow::ensure!(
+        !certs.is_empty(),
+        "No certificate found in {}",
+        cert_file.display()
+    );
+    let key_file = config.key_file();
+    let key = PrivateKeyDer::from_pem_file(key_file)
+        .with_context(|| format!("Failed to read private key {}", key_file.display()))?;
+    let key = ring::sign::any_supported_type(&key)
+        .with_context(|| format!("Unsupported private key {}", key_file.display()))?;
+    Ok(CertifiedKey::new(certs, key))
+}
+
+/// 监听证书和私钥文件，变化时重新加载
+fn watch(resolver: Arc<CertResolver>) -> anyhow::Result<()> {
+    let config = resolver.config;
+    let paths = vec![config.cert_file().clone(), config.key_file().clone()];
+    crate::config::watch_files(paths, RELOAD_DEBOUNCE, move || resolver.reload())
+}
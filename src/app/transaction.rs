@@ -0,0 +1,198 @@
+use std::{
+    ops::Deref,
+    sync::{Arc, Mutex, PoisonError},
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use axum::{
+    extract::{FromRequest, FromRequestParts, Request},
+    http::request::Parts,
+    response::{IntoResponse, Response},
+};
+use futures::future::BoxFuture;
+use sea_orm::{DatabaseTransaction, IsolationLevel, TransactionTrait};
+use tower::{Layer, Service};
+
+use crate::app::{ApiError, AppState, metrics};
+
+/// 请求级数据库事务，需要在路由上添加 [`TransactionLayer`]
+///
+/// 第一次提取时开启事务，同一请求内多次提取得到同一个事务；
+/// handler 返回成功响应时提交，返回错误（非 2xx/3xx）时回滚
+pub struct Tx(Arc<DatabaseTransaction>);
+
+impl Deref for Tx {
+    type Target = DatabaseTransaction;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl FromRequestParts<AppState> for Tx {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        Tx::begin(TxSlot::from_extensions(&parts.extensions)?, state).await
+    }
+}
+
+impl Tx {
+    async fn begin(slot: TxSlot, state: &AppState) -> Result<Self, ApiError> {
+        if let Some(txn) = slot.get() {
+            return Ok(Tx(txn));
+        }
+        let isolation_level = slot
+            .isolation_level
+            .or_else(|| crate::config::get().database().isolation_level());
+        let start = Instant::now();
+        let txn = Arc::new(
+            state
+                .db
+                .write()
+                .begin_with_config(isolation_level, None)
+                .await?,
+        );
+        metrics::observe_db_transaction_begin(start.elapsed());
+        slot.set(txn.clone());
+        Ok(Tx(txn))
+    }
+}
+
+/// 先提取请求体（例如 [`ValidJson`](crate::app::ValidJson)）再开启事务，请求体无效时不会开启事务
+///
+/// axum 中请求体提取器只能放在最后，直接写成 `(tx: Tx, ValidJson(dto): ValidJson<T>)` 时会先开启事务
+pub struct WithTx<T>(pub T, pub Tx);
+
+impl<T> FromRequest<AppState> for WithTx<T>
+where
+    T: FromRequest<AppState, Rejection = ApiError> + Send,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request, state: &AppState) -> Result<Self, Self::Rejection> {
+        let slot = TxSlot::from_extensions(request.extensions())?;
+        let body = T::from_request(request, state).await?;
+        Ok(WithTx(body, Tx::begin(slot, state).await?))
+    }
+}
+
+/// 为路由开启请求级事务，配合 [`Tx`] 提取器使用
+#[derive(Debug, Clone, Default)]
+pub struct TransactionLayer {
+    isolation_level: Option<IsolationLevel>,
+}
+
+impl TransactionLayer {
+    /// 使用 `database.isolation_level` 配置的隔离级别
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// 覆盖该路由的隔离级别
+    #[allow(dead_code)]
+    pub fn isolation_level(mut self, isolation_level: IsolationLevel) -> Self {
+        self.isolation_level = Some(isolation_level);
+        self
+    }
+}
+
+impl<S> Layer<S> for TransactionLayer {
+    type Service = TransactionService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TransactionService {
+            inner,
+            isolation_level: self.isolation_level,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TransactionService<S> {
+    inner: S,
+    isolation_level: Option<IsolationLevel>,
+}
+
+impl<S> Service<Request> for TransactionService<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request) -> Self::Future {
+        let slot = TxSlot {
+            isolation_level: self.isolation_level,
+            txn: Arc::default(),
+        };
+        request.extensions_mut().insert(slot.clone());
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        Box::pin(async move {
+            let response = inner.call(request).await?;
+            Ok(slot.finish(response).await)
+        })
+    }
+}
+
+#[derive(Clone)]
+struct TxSlot {
+    isolation_level: Option<IsolationLevel>,
+    txn: Arc<Mutex<Option<Arc<DatabaseTransaction>>>>,
+}
+
+impl TxSlot {
+    fn from_extensions(extensions: &axum::http::Extensions) -> Result<Self, ApiError> {
+        extensions.get::<TxSlot>().cloned().ok_or_else(|| {
+            ApiError::InternalServerError(anyhow::anyhow!(
+                "Tx extractor requires TransactionLayer on the route"
+            ))
+        })
+    }
+    fn get(&self) -> Option<Arc<DatabaseTransaction>> {
+        self.txn
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+    fn set(&self, txn: Arc<DatabaseTransaction>) {
+        *self.txn.lock().unwrap_or_else(PoisonError::into_inner) = Some(txn);
+    }
+    /// 根据响应状态提交或回滚事务，提交失败时返回错误响应
+    async fn finish(self, response: Response) -> Response {
+        let Some(txn) = self
+            .txn
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+        else {
+            return response;
+        };
+        let Ok(txn) = Arc::try_unwrap(txn) else {
+            // 事务仍被持有（例如被移入后台任务），drop 时自动回滚
+            return ApiError::InternalServerError(anyhow::anyhow!(
+                "transaction is still in use after the handler returned"
+            ))
+            .into_response();
+        };
+        let status = response.status();
+        if status.is_success() || status.is_redirection() {
+            if let Err(err) = txn.commit().await {
+                return ApiError::from(err).into_response();
+            }
+        } else if let Err(err) = txn.rollback().await {
+            tracing::error!("Failed to rollback transaction: {}", err);
+        }
+        response
+    }
+}
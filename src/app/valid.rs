@@ -17,6 +17,7 @@ impl<T> HasValidate for Valid<T> {
 #[derive(Debug, Clone, Default)]
 pub struct ValidQuery<T>(pub T);
 
+#[allow(dead_code)]
 #[derive(Debug, Clone, Default)]
 pub struct ValidPath<T>(pub T);
 
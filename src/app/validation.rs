@@ -1,8 +1,8 @@
-use std::{borrow::Cow, cell::LazyCell, collections::HashMap};
+use std::{borrow::Cow, collections::HashMap, sync::LazyLock};
 
 use validator::ValidationError;
 
-const MOBILE_PHONE_REGEX: LazyCell<regex::Regex> = LazyCell::new(|| {
+static MOBILE_PHONE_REGEX: LazyLock<regex::Regex> = LazyLock::new(|| {
     regex::Regex::new(r"^1[3456789]\d{9}$")
         .unwrap_or_else(|e| panic!("Failed to compile mobile phone regex: {}", e))
 });
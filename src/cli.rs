@@ -0,0 +1,177 @@
+use std::{
+    io::{BufRead, IsTerminal, Write},
+    path::PathBuf,
+};
+
+use anyhow::Context;
+use clap::{Args, Parser, Subcommand};
+use sea_orm::{ActiveModelTrait, ActiveValue, prelude::Date};
+
+use crate::{
+    app::{self, Gender},
+    config::{self, ConfigOptions},
+    entity::sys_user,
+    migration::MigrateCommand,
+    utils::crypt,
+};
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Path of the configuration file (overrides --config-dir)
+    #[arg(short, long, global = true, env = "APP_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Directory containing application.yaml and application-{profile}.yaml
+    #[arg(long, global = true, env = "APP_CONFIG_DIR")]
+    config_dir: Option<PathBuf>,
+
+    /// Configuration profile to activate, e.g. dev / test / prod
+    #[arg(short, long, global = true, env = "APP_PROFILE")]
+    profile: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Start the HTTP server (default)
+    Serve(ServeArgs),
+    /// Load and validate the configuration, then exit
+    CheckConfig,
+    /// Create an administrator account (password from APP_ADMIN_PASSWORD or stdin)
+    CreateAdmin(CreateAdminArgs),
+    /// Hash a password with bcrypt (password from stdin)
+    HashPassword,
+    /// Run database migrations
+    Migrate {
+        #[command(subcommand)]
+        command: MigrateCommand,
+    },
+}
+
+#[derive(Debug, Default, Args)]
+struct ServeArgs {
+    /// Override server.port
+    #[arg(long)]
+    port: Option<u16>,
+}
+
+#[derive(Debug, Args)]
+struct CreateAdminArgs {
+    #[arg(long)]
+    account: String,
+    #[arg(long, default_value = "admin")]
+    username: String,
+    #[arg(long)]
+    mobile_phone: String,
+    #[arg(long, value_parser = parse_gender, default_value = "male")]
+    gender: Gender,
+    #[arg(long, default_value = "1970-01-01")]
+    birthday: Date,
+}
+
+impl Cli {
+    pub async fn run(self) -> anyhow::Result<()> {
+        let command = self.command.unwrap_or(Command::Serve(ServeArgs::default()));
+        let mut options = ConfigOptions {
+            dir: self.config_dir,
+            file: self.config,
+            profile: self.profile,
+            port: None,
+        };
+        if let Command::Serve(args) = &command {
+            options.port = args.port;
+        }
+        // hash-password 和 check-config 不需要先初始化配置
+        match command {
+            Command::HashPassword => hash_password(),
+            Command::CheckConfig => check_config(&options),
+            Command::Serve(_) => {
+                config::init(&options)?;
+                app::run(crate::api::create_router()).await
+            }
+            Command::Migrate { command } => {
+                config::init(&options)?;
+                crate::migration::run(&app::bootstrap().await?, command).await
+            }
+            Command::CreateAdmin(args) => {
+                config::init(&options)?;
+                create_admin(args).await
+            }
+        }
+    }
+}
+
+fn check_config(options: &ConfigOptions) -> anyhow::Result<()> {
+    let config = config::init(options)?;
+    println!(
+        "Configuration is valid (profile: {}, server port: {}, database: {}:{}/{})",
+        config.profile().unwrap_or("default"),
+        config.server().port(),
+        config.database().host(),
+        config.database().port(),
+        config.database().database()
+    );
+    Ok(())
+}
+
+/// 密码只从标准输入读取，避免出现在进程列表和 shell 历史中
+fn hash_password() -> anyhow::Result<()> {
+    println!("{}", crypt::encode_password(read_password()?)?);
+    Ok(())
+}
+
+/// 从标准输入读取一行密码，终端中会先输出提示
+fn read_password() -> anyhow::Result<String> {
+    let stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        eprint!("Password: ");
+        std::io::stderr().flush()?;
+    }
+    let password = stdin
+        .lock()
+        .lines()
+        .next()
+        .context("No password given on stdin")??;
+    Ok(password.trim_end().to_string())
+}
+
+/// 密码只从环境变量 APP_ADMIN_PASSWORD 或标准输入读取，避免出现在进程列表和 shell 历史中
+async fn create_admin(args: CreateAdminArgs) -> anyhow::Result<()> {
+    let password = match std::env::var("APP_ADMIN_PASSWORD") {
+        Ok(password) => password,
+        Err(_) => read_password()?,
+    };
+    anyhow::ensure!(
+        (6..=20).contains(&password.len()),
+        "Password length must be between 6 and 20"
+    );
+    app::is_mobile_phone(&args.mobile_phone)
+        .map_err(|_| anyhow::anyhow!("Invalid mobile phone: {}", args.mobile_phone))?;
+    let db = app::bootstrap().await?;
+    let admin = sys_user::ActiveModel {
+        username: ActiveValue::Set(args.username),
+        gender: ActiveValue::Set(args.gender),
+        account: ActiveValue::Set(args.account),
+        password: ActiveValue::Set(crypt::encode_password(&password)?),
+        mobile_phone: ActiveValue::Set(args.mobile_phone),
+        birthday: ActiveValue::Set(args.birthday),
+        enbaled: ActiveValue::Set(true),
+        ..Default::default()
+    }
+    .insert(&db)
+    .await
+    .map_err(app::ApiError::from)?;
+    tracing::info!("Admin {} created with id {}", admin.account, admin.id);
+    Ok(())
+}
+
+fn parse_gender(value: &str) -> Result<Gender, String> {
+    match value {
+        "male" => Ok(Gender::Male),
+        "female" => Ok(Gender::Female),
+        _ => Err(String::from("gender must be male or female")),
+    }
+}
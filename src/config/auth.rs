@@ -0,0 +1,66 @@
+use std::{path::PathBuf, time::Duration};
+
+use serde::Deserialize;
+
+use crate::config::{secret::Secret, validation::ConfigValidator};
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct AuthConfig {
+    jwt_secret: Option<Secret<String>>,
+    jwt_secret_file: Option<PathBuf>,
+    jwt_expiration: Option<u64>,
+    jwt_audience: Option<String>,
+    jwt_issuer: Option<String>,
+    /// 可以访问管理接口的账号
+    #[serde(default)]
+    admin_accounts: Vec<String>,
+}
+
+impl AuthConfig {
+    /// 除热加载生效的 jwt_expiration、admin_accounts 外是否有变化
+    pub(super) fn requires_restart(&self, other: &Self) -> bool {
+        *self
+            != Self {
+                jwt_expiration: self.jwt_expiration,
+                admin_accounts: self.admin_accounts.clone(),
+                ..other.clone()
+            }
+    }
+    pub fn jwt_secret(&self) -> Option<&str> {
+        self.jwt_secret
+            .as_ref()
+            .map(|secret| secret.expose().as_str())
+    }
+    pub fn jwt_expiration(&self) -> Option<Duration> {
+        self.jwt_expiration.map(Duration::from_secs)
+    }
+    pub fn jwt_audience(&self) -> Option<&str> {
+        self.jwt_audience.as_deref()
+    }
+    pub fn jwt_issuer(&self) -> Option<&str> {
+        self.jwt_issuer.as_deref()
+    }
+    pub fn is_admin(&self, account: &str) -> bool {
+        self.admin_accounts.iter().any(|admin| admin == account)
+    }
+    pub(super) fn resolve_secrets(&mut self) -> anyhow::Result<()> {
+        if let Some(file) = &self.jwt_secret_file {
+            self.jwt_secret = Some(Secret::from_file(file)?);
+        }
+        Ok(())
+    }
+    pub(super) fn validate(&self, validator: &mut ConfigValidator) {
+        validator.check(
+            self.jwt_expiration != Some(0),
+            "auth.jwt_expiration",
+            "must be greater than 0",
+        );
+        if validator.is_prod() {
+            validator.check(
+                self.jwt_secret().is_some_and(|secret| !secret.is_empty()),
+                "auth.jwt_secret",
+                "must be set in prod",
+            );
+        }
+    }
+}
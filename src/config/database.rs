@@ -1,28 +1,178 @@
+use std::{cmp::max, path::PathBuf, str::FromStr, time::Duration};
+
+use anyhow::Context;
+use sea_orm::IsolationLevel;
 use serde::Deserialize;
+use url::Url;
+
+use crate::config::{secret::Secret, validation::ConfigValidator};
+
+const ISOLATION_LEVELS: [(&str, IsolationLevel); 4] = [
+    ("read_uncommitted", IsolationLevel::ReadUncommitted),
+    ("read_committed", IsolationLevel::ReadCommitted),
+    ("repeatable_read", IsolationLevel::RepeatableRead),
+    ("serializable", IsolationLevel::Serializable),
+];
+
+const SSL_MODES: [&str; 6] = [
+    "disable",
+    "allow",
+    "prefer",
+    "require",
+    "verify-ca",
+    "verify-full",
+];
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct DatabaseConfig {
+    /// 完整连接串，设置后忽略 host/port/username/password/database 及 ssl 等连接参数
+    url: Option<Secret<String>>,
     host: Option<String>,
     port: Option<u16>,
     username: Option<String>,
-    password: Option<String>,
+    password: Option<Secret<String>>,
+    password_file: Option<PathBuf>,
     database: Option<String>,
     schema: Option<String>,
     timeout: Option<u64>,
+    auto_migrate: Option<bool>,
+    max_connections: Option<u32>,
+    min_connections: Option<u32>,
+    acquire_timeout: Option<u64>,
+    idle_timeout: Option<u64>,
+    max_lifetime: Option<u64>,
+    sqlx_logging: Option<bool>,
+    sqlx_logging_level: Option<String>,
+    ssl_mode: Option<String>,
+    ssl_root_cert: Option<PathBuf>,
+    application_name: Option<String>,
+    statement_timeout: Option<u64>,
+    isolation_level: Option<String>,
+    #[serde(default)]
+    retry: RetryConfig,
+    #[serde(default)]
+    slow_query: SlowQueryConfig,
+    /// 只读副本，未配置的连接参数沿用主库
+    #[serde(default)]
+    replicas: Vec<ReplicaConfig>,
+    replica_health_check_interval: Option<u64>,
+}
+
+/// 只读副本连接配置
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ReplicaConfig {
+    /// 完整连接串，设置后忽略 host/port
+    url: Option<Secret<String>>,
+    host: Option<String>,
+    port: Option<u16>,
+}
+
+impl ReplicaConfig {
+    /// 生成副本连接串：未设置 url 时替换主库连接串中的 host/port
+    fn connection_url(&self, primary_url: &str) -> anyhow::Result<String> {
+        if let Some(url) = &self.url {
+            return Ok(url.expose().to_string());
+        }
+        let mut url = Url::parse(primary_url)?;
+        if let Some(host) = &self.host {
+            url.set_host(Some(host))
+                .with_context(|| format!("Invalid replica host {host}"))?;
+        }
+        if let Some(port) = self.port {
+            url.set_port(Some(port))
+                .map_err(|_| anyhow::anyhow!("Invalid replica port {port}"))?;
+        }
+        Ok(url.into())
+    }
+}
+
+/// 启动时连接数据库的重试策略（指数退避 + 随机抖动）
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct RetryConfig {
+    max_attempts: Option<u32>,
+    initial_backoff: Option<u64>,
+    max_backoff: Option<u64>,
+    max_wait: Option<u64>,
+    start_degraded: Option<bool>,
+}
+
+impl RetryConfig {
+    /// 最大尝试次数，0 表示只受 max_wait 限制
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts.unwrap_or(0)
+    }
+    /// 首次重试等待时间（毫秒），之后每次翻倍
+    pub fn initial_backoff(&self) -> Duration {
+        Duration::from_millis(self.initial_backoff.unwrap_or(500))
+    }
+    /// 单次重试最长等待时间（毫秒）
+    pub fn max_backoff(&self) -> Duration {
+        Duration::from_millis(self.max_backoff.unwrap_or(10_000))
+    }
+    /// 总共最长等待时间（秒），超过后启动失败
+    pub fn max_wait(&self) -> Duration {
+        Duration::from_secs(self.max_wait.unwrap_or(60))
+    }
+    /// 数据库不可用时先启动 HTTP 服务（未就绪状态），后台持续重连
+    pub fn start_degraded(&self) -> bool {
+        self.start_degraded.unwrap_or(false)
+    }
+    fn validate(&self, validator: &mut ConfigValidator) {
+        validator.check(
+            !self.initial_backoff().is_zero(),
+            "database.retry.initial_backoff",
+            "must be greater than 0",
+        );
+        validator.check(
+            self.initial_backoff() <= self.max_backoff(),
+            "database.retry.max_backoff",
+            "must not be less than initial_backoff",
+        );
+    }
+}
+
+/// 慢 SQL 告警，日志中只输出带占位符的 SQL，不输出参数值
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct SlowQueryConfig {
+    enabled: Option<bool>,
+    threshold: Option<u64>,
+}
+
+impl SlowQueryConfig {
+    pub fn enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+    /// 阈值（毫秒）
+    pub fn threshold(&self) -> Duration {
+        Duration::from_millis(self.threshold.unwrap_or(500))
+    }
+    fn validate(&self, validator: &mut ConfigValidator) {
+        validator.check(
+            !self.threshold().is_zero(),
+            "database.slow_query.threshold",
+            "must be greater than 0",
+        );
+    }
 }
 
 impl DatabaseConfig {
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_ref().map(|url| url.expose().as_str())
+    }
     pub fn host(&self) -> String {
         self.host.clone().unwrap_or("localhost".to_string())
     }
     pub fn port(&self) -> u16 {
-        self.port.clone().unwrap_or(5432)
+        self.port.unwrap_or(5432)
     }
     pub fn username(&self) -> String {
         self.username.clone().unwrap_or("postgres".to_string())
     }
-    pub fn password(&self) -> String {
-        self.password.clone().unwrap_or("".to_string())
+    pub fn password(&self) -> &str {
+        self.password
+            .as_ref()
+            .map(|password| password.expose().as_str())
+            .unwrap_or("")
     }
     pub fn database(&self) -> String {
         self.database.clone().unwrap_or("postgres".to_string())
@@ -30,7 +180,320 @@ impl DatabaseConfig {
     pub fn schema(&self) -> String {
         self.schema.clone().unwrap_or("public".to_string())
     }
+    /// 连接超时（秒）
     pub fn timeout(&self) -> u64 {
-        self.timeout.clone().unwrap_or(5)
+        self.timeout.unwrap_or(5)
+    }
+    pub fn auto_migrate(&self) -> bool {
+        self.auto_migrate.unwrap_or(false)
+    }
+    pub fn max_connections(&self) -> u32 {
+        self.max_connections
+            .unwrap_or_else(|| max(num_cpus::get() as u32 * 8, 20))
+    }
+    pub fn min_connections(&self) -> u32 {
+        self.min_connections
+            .unwrap_or_else(|| max(num_cpus::get() as u32 * 4, 10).min(self.max_connections()))
+    }
+    pub fn acquire_timeout(&self) -> Duration {
+        Duration::from_secs(self.acquire_timeout.unwrap_or(30))
+    }
+    pub fn idle_timeout(&self) -> Duration {
+        Duration::from_secs(self.idle_timeout.unwrap_or(300))
+    }
+    pub fn max_lifetime(&self) -> Duration {
+        Duration::from_secs(self.max_lifetime.unwrap_or(3600 * 24))
+    }
+    pub fn sqlx_logging(&self) -> bool {
+        self.sqlx_logging.unwrap_or(false)
+    }
+    pub fn sqlx_logging_level(&self) -> log::LevelFilter {
+        self.sqlx_logging_level
+            .as_deref()
+            .and_then(|level| log::LevelFilter::from_str(level).ok())
+            .unwrap_or(log::LevelFilter::Info)
+    }
+    pub fn ssl_mode(&self) -> &str {
+        self.ssl_mode.as_deref().unwrap_or("prefer")
+    }
+    pub fn application_name(&self) -> &str {
+        self.application_name
+            .as_deref()
+            .unwrap_or(env!("CARGO_PKG_NAME"))
+    }
+    /// 单条语句超时（毫秒），不设置时使用数据库默认值
+    pub fn statement_timeout(&self) -> Option<u64> {
+        self.statement_timeout
+    }
+    /// 请求级事务默认隔离级别，不设置时使用数据库默认值
+    pub fn isolation_level(&self) -> Option<IsolationLevel> {
+        self.isolation_level.as_deref().and_then(|level| {
+            ISOLATION_LEVELS
+                .iter()
+                .find(|(name, _)| *name == level)
+                .map(|(_, level)| *level)
+        })
+    }
+    /// 生成连接串，用户名、密码和库名会做 URL 编码
+    pub fn connection_url(&self) -> anyhow::Result<String> {
+        if let Some(url) = self.url() {
+            return Ok(url.to_string());
+        }
+        let mut url = Url::parse("postgres://localhost")?;
+        url.set_host(Some(&self.host()))
+            .with_context(|| format!("Invalid database host {}", self.host()))?;
+        url.set_port(Some(self.port()))
+            .map_err(|_| anyhow::anyhow!("Invalid database port {}", self.port()))?;
+        url.set_username(&self.username())
+            .map_err(|_| anyhow::anyhow!("Invalid database username"))?;
+        if !self.password().is_empty() {
+            url.set_password(Some(self.password()))
+                .map_err(|_| anyhow::anyhow!("Invalid database password"))?;
+        }
+        url.path_segments_mut()
+            .map_err(|_| anyhow::anyhow!("Invalid database url"))?
+            .push(&self.database());
+        {
+            let mut query = url.query_pairs_mut();
+            query
+                .append_pair("sslmode", self.ssl_mode())
+                .append_pair("application_name", self.application_name());
+            if let Some(ssl_root_cert) = &self.ssl_root_cert {
+                query.append_pair("sslrootcert", &ssl_root_cert.to_string_lossy());
+            }
+            if let Some(statement_timeout) = self.statement_timeout() {
+                query.append_pair(
+                    "options",
+                    &format!("-c statement_timeout={statement_timeout}"),
+                );
+            }
+        }
+        Ok(url.into())
+    }
+    pub fn retry(&self) -> &RetryConfig {
+        &self.retry
+    }
+    pub fn slow_query(&self) -> &SlowQueryConfig {
+        &self.slow_query
+    }
+    /// 除热加载生效的 slow_query 外是否有变化
+    pub(super) fn requires_restart(&self, other: &Self) -> bool {
+        *self
+            != Self {
+                slow_query: self.slow_query.clone(),
+                ..other.clone()
+            }
+    }
+    /// 所有只读副本的连接串
+    pub fn replica_urls(&self) -> anyhow::Result<Vec<String>> {
+        let primary_url = self.connection_url()?;
+        self.replicas
+            .iter()
+            .map(|replica| replica.connection_url(&primary_url))
+            .collect()
+    }
+    /// 副本健康检查间隔（秒）
+    pub fn replica_health_check_interval(&self) -> Duration {
+        Duration::from_secs(self.replica_health_check_interval.unwrap_or(10))
+    }
+    pub(super) fn resolve_secrets(&mut self) -> anyhow::Result<()> {
+        if let Some(file) = &self.password_file {
+            self.password = Some(Secret::from_file(file)?);
+        }
+        Ok(())
+    }
+    pub(super) fn validate(&self, validator: &mut ConfigValidator) {
+        if let Some(url) = self.url() {
+            validator.check(
+                Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "postgres" | "postgresql")),
+                "database.url",
+                "must be a valid postgres:// url",
+            );
+        }
+        validator.check(
+            !self.host().is_empty(),
+            "database.host",
+            "must not be empty",
+        );
+        validator.check(
+            self.port() != 0,
+            "database.port",
+            "must be between 1 and 65535",
+        );
+        validator.check(
+            !self.username().is_empty(),
+            "database.username",
+            "must not be empty",
+        );
+        validator.check(
+            !self.database().is_empty(),
+            "database.database",
+            "must not be empty",
+        );
+        validator.check(
+            self.timeout() > 0,
+            "database.timeout",
+            "must be greater than 0",
+        );
+        validator.check(
+            self.max_connections() > 0,
+            "database.max_connections",
+            "must be greater than 0",
+        );
+        validator.check(
+            self.min_connections() <= self.max_connections(),
+            "database.min_connections",
+            "must not be greater than max_connections",
+        );
+        if let Some(level) = &self.sqlx_logging_level {
+            validator.check(
+                log::LevelFilter::from_str(level).is_ok(),
+                "database.sqlx_logging_level",
+                "must be one of off, error, warn, info, debug, trace",
+            );
+        }
+        validator.check(
+            SSL_MODES.contains(&self.ssl_mode()),
+            "database.ssl_mode",
+            format!("must be one of {}", SSL_MODES.join(", ")),
+        );
+        if let Some(ssl_root_cert) = &self.ssl_root_cert {
+            validator.check(
+                ssl_root_cert.is_file(),
+                "database.ssl_root_cert",
+                format!("file {} does not exist", ssl_root_cert.display()),
+            );
+        }
+        if let Some(level) = &self.isolation_level {
+            validator.check(
+                ISOLATION_LEVELS.iter().any(|(name, _)| name == level),
+                "database.isolation_level",
+                format!(
+                    "must be one of {}",
+                    ISOLATION_LEVELS.map(|(name, _)| name).join(", ")
+                ),
+            );
+        }
+        self.retry.validate(validator);
+        self.slow_query.validate(validator);
+        for (index, replica) in self.replicas.iter().enumerate() {
+            let key = format!("database.replicas[{index}]");
+            if let Some(url) = &replica.url {
+                validator.check(
+                    Url::parse(url.expose())
+                        .is_ok_and(|url| matches!(url.scheme(), "postgres" | "postgresql")),
+                    &format!("{key}.url"),
+                    "must be a valid postgres:// url",
+                );
+            } else {
+                validator.check(
+                    replica.host.is_some() || replica.port.is_some(),
+                    &key,
+                    "must set url, host or port",
+                );
+                validator.check(
+                    replica.port != Some(0),
+                    &format!("{key}.port"),
+                    "must be between 1 and 65535",
+                );
+            }
+        }
+        validator.check(
+            !self.replica_health_check_interval().is_zero(),
+            "database.replica_health_check_interval",
+            "must be greater than 0",
+        );
+        if validator.is_prod() {
+            validator.check(
+                self.url().is_some() || !self.password().is_empty(),
+                "database.password",
+                "must not be empty in prod",
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(yaml: &str) -> DatabaseConfig {
+        config::Config::builder()
+            .add_source(config::File::from_str(yaml, config::FileFormat::Yaml))
+            .build()
+            .and_then(|config| config.try_deserialize())
+            .unwrap()
+    }
+
+    #[test]
+    fn encodes_credentials_and_database() {
+        let url = config(
+            "host: db.internal\nport: 6543\nusername: app@user\npassword: 'p@ss:w/rd?#'\ndatabase: my db",
+        )
+        .connection_url()
+        .unwrap();
+        let parsed = Url::parse(&url).unwrap();
+        assert_eq!(parsed.host_str(), Some("db.internal"));
+        assert_eq!(parsed.port(), Some(6543));
+        assert_eq!(parsed.username(), "app%40user");
+        assert_eq!(parsed.password(), Some("p%40ss%3Aw%2Frd%3F%23"));
+        assert_eq!(parsed.path(), "/my%20db");
+    }
+
+    #[test]
+    fn appends_connection_options() {
+        let url = config(
+            "ssl_mode: require\nssl_root_cert: /etc/ca.pem\napplication_name: api\nstatement_timeout: 3000",
+        )
+        .connection_url()
+        .unwrap();
+        let query = Url::parse(&url)
+            .unwrap()
+            .query_pairs()
+            .into_owned()
+            .collect::<Vec<_>>();
+        let value = |key: &str| {
+            query
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value.as_str())
+        };
+        assert_eq!(value("sslmode"), Some("require"));
+        assert_eq!(value("sslrootcert"), Some("/etc/ca.pem"));
+        assert_eq!(value("application_name"), Some("api"));
+        assert_eq!(value("options"), Some("-c statement_timeout=3000"));
+    }
+
+    #[test]
+    fn url_overrides_connection_parameters() {
+        let config = config("url: postgres://u:p@primary:5432/app\nhost: ignored");
+        assert_eq!(
+            config.connection_url().unwrap(),
+            "postgres://u:p@primary:5432/app"
+        );
+    }
+
+    #[test]
+    fn replicas_inherit_primary_settings() {
+        let urls = config(
+            "url: postgres://u:p@primary:5432/app?sslmode=require\nreplicas:\n  - host: replica-1\n  - host: replica-2\n    port: 6432\n  - url: postgres://r:p@replica-3/app",
+        )
+        .replica_urls()
+        .unwrap();
+        assert_eq!(
+            urls,
+            [
+                "postgres://u:p@replica-1:5432/app?sslmode=require",
+                "postgres://u:p@replica-2:6432/app?sslmode=require",
+                "postgres://r:p@replica-3/app",
+            ]
+        );
+    }
+
+    #[test]
+    fn slow_query_change_does_not_require_restart() {
+        let old = config("host: db\nslow_query:\n  threshold: 500");
+        assert!(!old.requires_restart(&config("host: db\nslow_query:\n  threshold: 100")));
+        assert!(old.requires_restart(&config("host: other\nslow_query:\n  threshold: 500")));
     }
 }
@@ -0,0 +1,197 @@
+use std::{collections::BTreeMap, path::PathBuf, str::FromStr};
+
+use bytesize::ByteSize;
+use serde::Deserialize;
+use tracing_subscriber::EnvFilter;
+
+use crate::config::validation::ConfigValidator;
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct LoggingConfig {
+    level: Option<String>,
+    /// 按模块设置日志级别，例如 `sqlx: warn`
+    #[serde(default)]
+    targets: BTreeMap<String, String>,
+    #[serde(default)]
+    format: LogFormat,
+    file: Option<LogFileConfig>,
+    #[serde(default)]
+    redact: RedactConfig,
+}
+
+/// 日志输出格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    /// 便于阅读的文本格式
+    #[default]
+    Pretty,
+    /// 每行一个 JSON 对象，便于日志采集
+    Json,
+}
+
+/// 按时间切分的周期
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogRotation {
+    #[default]
+    Daily,
+    Hourly,
+    /// 只按大小切分
+    Never,
+}
+
+/// 日志文件输出，按时间或大小切分，只保留最近的 max_files 个历史文件
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct LogFileConfig {
+    enabled: Option<bool>,
+    path: Option<PathBuf>,
+    format: Option<LogFormat>,
+    #[serde(default)]
+    rotation: LogRotation,
+    max_size: Option<String>,
+    max_files: Option<usize>,
+}
+
+impl LogFileConfig {
+    pub fn enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+    pub fn path(&self) -> PathBuf {
+        self.path
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("logs/axum-starter.log"))
+    }
+    pub fn rotation(&self) -> LogRotation {
+        self.rotation
+    }
+    /// 单个文件超过该大小时切分，不设置时只按时间切分
+    pub fn max_size(&self) -> Option<ByteSize> {
+        self.max_size
+            .as_deref()
+            .and_then(|size| ByteSize::from_str(size).ok())
+    }
+    /// 保留的历史文件数量
+    pub fn max_files(&self) -> usize {
+        self.max_files.unwrap_or(7)
+    }
+}
+
+/// 日志脱敏，同时作用于导出的 OpenTelemetry span 和返回给客户端的 4xx 错误信息
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct RedactConfig {
+    enabled: Option<bool>,
+    fields: Option<Vec<String>>,
+    mask_mobile_phone: Option<bool>,
+}
+
+impl RedactConfig {
+    /// 默认脱敏的字段名（不区分大小写）
+    const DEFAULT_FIELDS: [&str; 14] = [
+        "password",
+        "passwd",
+        "secret",
+        "jwt_secret",
+        "token",
+        "access_token",
+        "accessToken",
+        "refresh_token",
+        "refreshToken",
+        "authorization",
+        "api_key",
+        "x-api-key",
+        "mobile_phone",
+        "mobilePhone",
+    ];
+
+    pub fn enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+    /// 需要脱敏的字段名，`key=value` / `key: "value"` / `"key":"value"` 中的值会被替换为 ***
+    pub fn fields(&self) -> Vec<String> {
+        self.fields
+            .clone()
+            .unwrap_or_else(|| Self::DEFAULT_FIELDS.map(String::from).to_vec())
+    }
+    /// 手机号只保留前 3 位和后 4 位
+    pub fn mask_mobile_phone(&self) -> bool {
+        self.mask_mobile_phone.unwrap_or(true)
+    }
+}
+
+impl LoggingConfig {
+    /// 除热加载生效的 level、targets、redact 外是否有变化
+    pub(super) fn requires_restart(&self, other: &Self) -> bool {
+        *self
+            != Self {
+                level: self.level.clone(),
+                targets: self.targets.clone(),
+                redact: self.redact.clone(),
+                ..other.clone()
+            }
+    }
+    /// EnvFilter 语法，例如 `info,sqlx=warn`，RUST_LOG 优先
+    pub fn level(&self) -> &str {
+        self.level.as_deref().unwrap_or("info")
+    }
+    /// level 和 targets 合并后的 EnvFilter
+    pub fn filter(&self) -> String {
+        std::iter::once(self.level().to_string())
+            .chain(
+                self.targets
+                    .iter()
+                    .map(|(target, level)| format!("{target}={level}")),
+            )
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+    pub fn format(&self) -> LogFormat {
+        self.format
+    }
+    /// 未设置格式时和控制台保持一致
+    pub fn file_format(&self) -> LogFormat {
+        self.file
+            .as_ref()
+            .and_then(|file| file.format)
+            .unwrap_or(self.format)
+    }
+    pub fn redact(&self) -> &RedactConfig {
+        &self.redact
+    }
+    pub fn file(&self) -> Option<&LogFileConfig> {
+        self.file.as_ref().filter(|file| file.enabled())
+    }
+    pub(super) fn validate(&self, validator: &mut ConfigValidator) {
+        if let Err(err) = EnvFilter::try_new(self.level()) {
+            validator.check(false, "logging.level", err);
+        }
+        for (target, level) in &self.targets {
+            validator.check(
+                EnvFilter::try_new(format!("{target}={level}")).is_ok(),
+                &format!("logging.targets.{target}"),
+                format!("invalid level {level}"),
+            );
+        }
+        for field in self.redact.fields() {
+            validator.check(
+                !field.trim().is_empty(),
+                "logging.redact.fields",
+                "must not contain empty field names",
+            );
+        }
+        if let Some(file) = &self.file {
+            if let Some(size) = &file.max_size {
+                validator.check(
+                    ByteSize::from_str(size).is_ok_and(|size| size.as_u64() > 0),
+                    "logging.file.max_size",
+                    "must be a size such as 100MiB",
+                );
+            }
+            validator.check(
+                file.max_files() > 0,
+                "logging.file.max_files",
+                "must be greater than 0",
+            );
+        }
+    }
+}
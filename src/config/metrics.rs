@@ -0,0 +1,45 @@
+use serde::Deserialize;
+
+use crate::config::{secret::Secret, validation::ConfigValidator};
+
+/// Prometheus 指标，默认关闭；token 修改后热加载生效，其余修改需要重启
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct MetricsConfig {
+    enabled: Option<bool>,
+    path: Option<String>,
+    token: Option<Secret<String>>,
+}
+
+impl MetricsConfig {
+    pub fn enabled(&self) -> bool {
+        self.enabled.unwrap_or(false)
+    }
+    /// 指标抓取地址
+    pub fn path(&self) -> &str {
+        self.path.as_deref().unwrap_or("/metrics")
+    }
+    /// 抓取时需要携带的 Bearer token，不设置时不鉴权
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_ref().map(|token| token.expose().as_str())
+    }
+    /// 除热加载生效的 token 外是否有变化
+    pub(super) fn requires_restart(&self, other: &Self) -> bool {
+        *self
+            != Self {
+                token: self.token.clone(),
+                ..other.clone()
+            }
+    }
+    pub(super) fn validate(&self, validator: &mut ConfigValidator) {
+        validator.check(
+            self.path().starts_with('/'),
+            "metrics.path",
+            "must start with /",
+        );
+        validator.check(
+            self.token().is_none_or(|token| !token.is_empty()),
+            "metrics.token",
+            "must not be empty",
+        );
+    }
+}
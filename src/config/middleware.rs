@@ -0,0 +1,470 @@
+use std::{collections::HashMap, str::FromStr, time::Duration};
+
+use axum::http::{HeaderName, HeaderValue, Method};
+use bytesize::ByteSize;
+use serde::Deserialize;
+
+use crate::config::{rate_limit::RateLimitConfig, validation::ConfigValidator};
+
+/// 允许任意来源 / 方法 / 请求头
+const WILDCARD: &str = "*";
+
+/// 全局中间件配置，每个中间件都可以单独关闭
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct MiddlewareConfig {
+    #[serde(default)]
+    timeout: TimeoutConfig,
+    #[serde(default)]
+    body_limit: BodyLimitConfig,
+    #[serde(default)]
+    cors: CorsConfig,
+    #[serde(default)]
+    rate_limit: RateLimitConfig,
+    #[serde(default)]
+    request_id: RequestIdConfig,
+    #[serde(default)]
+    body_log: BodyLogConfig,
+    #[serde(default)]
+    slow_request: SlowRequestConfig,
+    trace: Option<bool>,
+    normalize_path: Option<bool>,
+}
+
+impl MiddlewareConfig {
+    /// 除热加载生效的配置外是否有变化：timeout 的 duration / routes、cors 的 allowed_origins，
+    /// 以及 rate_limit、request_id、body_log、slow_request
+    pub(super) fn requires_restart(&self, other: &Self) -> bool {
+        *self
+            != Self {
+                timeout: TimeoutConfig {
+                    enabled: other.timeout.enabled,
+                    ..self.timeout.clone()
+                },
+                cors: CorsConfig {
+                    allowed_origins: self.cors.allowed_origins.clone(),
+                    ..other.cors.clone()
+                },
+                rate_limit: self.rate_limit.clone(),
+                request_id: self.request_id.clone(),
+                body_log: self.body_log.clone(),
+                slow_request: self.slow_request.clone(),
+                ..other.clone()
+            }
+    }
+    pub fn timeout(&self) -> &TimeoutConfig {
+        &self.timeout
+    }
+    pub fn body_limit(&self) -> &BodyLimitConfig {
+        &self.body_limit
+    }
+    pub fn cors(&self) -> &CorsConfig {
+        &self.cors
+    }
+    pub fn rate_limit(&self) -> &RateLimitConfig {
+        &self.rate_limit
+    }
+    pub fn request_id(&self) -> &RequestIdConfig {
+        &self.request_id
+    }
+    pub fn body_log(&self) -> &BodyLogConfig {
+        &self.body_log
+    }
+    pub fn slow_request(&self) -> &SlowRequestConfig {
+        &self.slow_request
+    }
+    /// 请求日志
+    pub fn trace(&self) -> bool {
+        self.trace.unwrap_or(true)
+    }
+    /// 去掉路径末尾的 /
+    pub fn normalize_path(&self) -> bool {
+        self.normalize_path.unwrap_or(true)
+    }
+    pub(super) fn validate(&self, validator: &mut ConfigValidator) {
+        self.timeout.validate(validator);
+        self.body_limit.validate(validator);
+        self.cors.validate(validator);
+        self.rate_limit.validate(validator);
+        self.request_id.validate(validator);
+        self.body_log.validate(validator);
+        self.slow_request.validate(validator);
+    }
+}
+
+/// 请求超时，`routes` 按路径前缀为路由分组单独设置超时（最长前缀优先）
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct TimeoutConfig {
+    enabled: Option<bool>,
+    duration: Option<u64>,
+    #[serde(default)]
+    routes: HashMap<String, u64>,
+}
+
+impl TimeoutConfig {
+    pub fn enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+    /// 默认超时时间（秒）
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs(self.duration.unwrap_or(120))
+    }
+    /// 请求路径对应的超时时间
+    pub fn duration_for(&self, path: &str) -> Duration {
+        longest_prefix(&self.routes, path)
+            .map(Duration::from_secs)
+            .unwrap_or_else(|| self.duration())
+    }
+    fn validate(&self, validator: &mut ConfigValidator) {
+        validator.check(
+            !self.duration().is_zero(),
+            "server.middleware.timeout.duration",
+            "must be greater than 0",
+        );
+        for (prefix, secs) in &self.routes {
+            let key = format!("server.middleware.timeout.routes.{prefix}");
+            validator.check(prefix.starts_with('/'), &key, "path must start with /");
+            validator.check(*secs > 0, &key, "must be greater than 0");
+        }
+    }
+}
+
+/// `/api/users` 匹配 `/api/users` 和 `/api/users/...`，不匹配 `/api/users2`
+pub(super) fn matches_prefix(path: &str, prefix: &str) -> bool {
+    let prefix = prefix.trim_end_matches('/');
+    path.strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// 按最长前缀查找路径对应的值，`/api/users/` 与 `/api/users` 视为同样长度，此时取字典序较大者保证结果稳定
+pub(super) fn longest_prefix<T: Copy>(routes: &HashMap<String, T>, path: &str) -> Option<T> {
+    routes
+        .iter()
+        .filter(|(prefix, _)| matches_prefix(path, prefix))
+        .max_by_key(|(prefix, _)| (prefix.trim_end_matches('/').len(), prefix.as_str()))
+        .map(|(_, value)| *value)
+}
+
+/// 请求 ID，请求头中没有合法的 ID 时自动生成
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct RequestIdConfig {
+    header: Option<String>,
+}
+
+impl RequestIdConfig {
+    /// 读取和返回请求 ID 的请求头
+    pub fn header(&self) -> HeaderName {
+        self.header
+            .as_deref()
+            .and_then(|header| HeaderName::from_str(header).ok())
+            .unwrap_or(HeaderName::from_static("x-request-id"))
+    }
+    fn validate(&self, validator: &mut ConfigValidator) {
+        if let Some(header) = &self.header {
+            validator.check(
+                HeaderName::from_str(header).is_ok(),
+                "server.middleware.request_id.header",
+                "must be a valid header name",
+            );
+        }
+    }
+}
+
+/// 慢请求告警，`routes` 按路径前缀单独设置阈值（最长前缀优先）
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct SlowRequestConfig {
+    enabled: Option<bool>,
+    threshold: Option<u64>,
+    #[serde(default)]
+    routes: HashMap<String, u64>,
+}
+
+impl SlowRequestConfig {
+    pub fn enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+    /// 默认阈值（毫秒）
+    pub fn threshold(&self) -> Duration {
+        Duration::from_millis(self.threshold.unwrap_or(1000))
+    }
+    /// 请求路径对应的阈值
+    pub fn threshold_for(&self, path: &str) -> Duration {
+        longest_prefix(&self.routes, path)
+            .map(Duration::from_millis)
+            .unwrap_or_else(|| self.threshold())
+    }
+    fn validate(&self, validator: &mut ConfigValidator) {
+        validator.check(
+            !self.threshold().is_zero(),
+            "server.middleware.slow_request.threshold",
+            "must be greater than 0",
+        );
+        for (prefix, millis) in &self.routes {
+            let key = format!("server.middleware.slow_request.routes.{prefix}");
+            validator.check(prefix.starts_with('/'), &key, "path must start with /");
+            validator.check(*millis > 0, &key, "must be greater than 0");
+        }
+    }
+}
+
+/// 请求体和响应体日志，只记录 JSON，`routes` 按路径前缀单独设置采样率（最长前缀优先，0 表示不记录）
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct BodyLogConfig {
+    enabled: Option<bool>,
+    max_size: Option<String>,
+    sample_ratio: Option<f64>,
+    #[serde(default)]
+    routes: HashMap<String, f64>,
+}
+
+impl BodyLogConfig {
+    pub fn enabled(&self) -> bool {
+        self.enabled.unwrap_or(false)
+    }
+    /// 单个请求体 / 响应体最多记录的大小，超出部分截断
+    pub fn max_size(&self) -> ByteSize {
+        self.max_size
+            .as_deref()
+            .and_then(|size| ByteSize::from_str(size).ok())
+            .unwrap_or(ByteSize::kib(4))
+    }
+    /// 默认采样率
+    pub fn sample_ratio(&self) -> f64 {
+        self.sample_ratio.unwrap_or(1.0)
+    }
+    /// 请求路径对应的采样率
+    pub fn sample_ratio_for(&self, path: &str) -> f64 {
+        longest_prefix(&self.routes, path).unwrap_or_else(|| self.sample_ratio())
+    }
+    fn validate(&self, validator: &mut ConfigValidator) {
+        if let Some(size) = &self.max_size {
+            validator.check(
+                ByteSize::from_str(size).is_ok_and(|size| size.as_u64() > 0),
+                "server.middleware.body_log.max_size",
+                "must be a size such as 4KiB",
+            );
+        }
+        validator.check(
+            (0.0..=1.0).contains(&self.sample_ratio()),
+            "server.middleware.body_log.sample_ratio",
+            "must be between 0.0 and 1.0",
+        );
+        for (prefix, ratio) in &self.routes {
+            let key = format!("server.middleware.body_log.routes.{prefix}");
+            validator.check(prefix.starts_with('/'), &key, "path must start with /");
+            validator.check(
+                (0.0..=1.0).contains(ratio),
+                &key,
+                "must be between 0.0 and 1.0",
+            );
+        }
+    }
+}
+
+/// 请求体大小限制
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct BodyLimitConfig {
+    enabled: Option<bool>,
+    max_size: Option<String>,
+}
+
+impl BodyLimitConfig {
+    /// 关闭后不限制请求体大小
+    pub fn enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+    /// 最大请求体，例如 10MiB / 512KB
+    pub fn max_size(&self) -> ByteSize {
+        self.max_size
+            .as_deref()
+            .and_then(|size| ByteSize::from_str(size).ok())
+            .unwrap_or(ByteSize::mib(10))
+    }
+    fn validate(&self, validator: &mut ConfigValidator) {
+        if let Some(size) = &self.max_size {
+            validator.check(
+                ByteSize::from_str(size).is_ok(),
+                "server.middleware.body_limit.max_size",
+                "must be a size such as 10MiB",
+            );
+        }
+    }
+}
+
+/// 跨域配置，列表中使用 `*` 表示允许任意值
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct CorsConfig {
+    enabled: Option<bool>,
+    allowed_origins: Option<Vec<String>>,
+    allowed_methods: Option<Vec<String>>,
+    allowed_headers: Option<Vec<String>>,
+    exposed_headers: Option<Vec<String>>,
+    allow_credentials: Option<bool>,
+    max_age: Option<u64>,
+}
+
+impl CorsConfig {
+    pub fn enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+    /// 允许的来源，None 表示任意来源
+    pub fn allowed_origins(&self) -> Option<Vec<HeaderValue>> {
+        parse_list(self.allowed_origins.as_deref(), |origin| {
+            HeaderValue::from_str(origin).ok()
+        })
+    }
+    /// 是否允许该来源，不设置或包含 `*` 时允许任意来源
+    pub fn allows_origin(&self, origin: &HeaderValue) -> bool {
+        self.allowed_origins.as_deref().is_none_or(|origins| {
+            origins
+                .iter()
+                .any(|allowed| allowed == WILDCARD || allowed.as_bytes() == origin.as_bytes())
+        })
+    }
+    /// 允许的方法，None 表示任意方法
+    pub fn allowed_methods(&self) -> Option<Vec<Method>> {
+        const DEFAULT_METHODS: [&str; 6] = ["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"];
+        let methods = self
+            .allowed_methods
+            .clone()
+            .unwrap_or_else(|| DEFAULT_METHODS.map(String::from).to_vec());
+        parse_list(Some(&methods), |method| {
+            Method::from_str(&method.to_uppercase()).ok()
+        })
+    }
+    /// 允许的请求头，None 表示任意请求头
+    pub fn allowed_headers(&self) -> Option<Vec<HeaderName>> {
+        parse_list(self.allowed_headers.as_deref(), |header| {
+            HeaderName::from_str(header).ok()
+        })
+    }
+    /// 允许前端读取的响应头
+    pub fn exposed_headers(&self) -> Vec<HeaderName> {
+        parse_list(self.exposed_headers.as_deref(), |header| {
+            HeaderName::from_str(header).ok()
+        })
+        .unwrap_or_default()
+    }
+    pub fn allow_credentials(&self) -> bool {
+        self.allow_credentials.unwrap_or(false)
+    }
+    /// 预检请求缓存时间（秒）
+    pub fn max_age(&self) -> Duration {
+        Duration::from_secs(self.max_age.unwrap_or(3600 * 12))
+    }
+    fn validate(&self, validator: &mut ConfigValidator) {
+        check_list(
+            validator,
+            "server.middleware.cors.allowed_origins",
+            self.allowed_origins.as_deref(),
+            |origin| HeaderValue::from_str(origin).is_ok(),
+        );
+        check_list(
+            validator,
+            "server.middleware.cors.allowed_methods",
+            self.allowed_methods.as_deref(),
+            |method| Method::from_str(&method.to_uppercase()).is_ok(),
+        );
+        check_list(
+            validator,
+            "server.middleware.cors.allowed_headers",
+            self.allowed_headers.as_deref(),
+            |header| HeaderName::from_str(header).is_ok(),
+        );
+        check_list(
+            validator,
+            "server.middleware.cors.exposed_headers",
+            self.exposed_headers.as_deref(),
+            |header| header != WILDCARD && HeaderName::from_str(header).is_ok(),
+        );
+        // 浏览器不允许携带凭证的请求使用通配符
+        if self.allow_credentials() {
+            validator.check(
+                self.allowed_origins().is_some()
+                    && self.allowed_methods().is_some()
+                    && self.allowed_headers().is_some(),
+                "server.middleware.cors.allow_credentials",
+                "can not be used with * in allowed_origins, allowed_methods or allowed_headers",
+            );
+        }
+    }
+}
+
+/// 解析列表，不设置或包含 `*` 时返回 None，忽略无法解析的值
+fn parse_list<T>(values: Option<&[String]>, parse: impl Fn(&str) -> Option<T>) -> Option<Vec<T>> {
+    let values = values?;
+    if values.iter().any(|value| value == WILDCARD) {
+        return None;
+    }
+    Some(values.iter().filter_map(|value| parse(value)).collect())
+}
+
+fn check_list(
+    validator: &mut ConfigValidator,
+    key: &str,
+    values: Option<&[String]>,
+    is_valid: impl Fn(&str) -> bool,
+) {
+    for value in values.unwrap_or_default() {
+        validator.check(
+            value == WILDCARD || is_valid(value),
+            key,
+            format!("invalid value {value}"),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cors(origins: Option<&[&str]>) -> CorsConfig {
+        CorsConfig {
+            allowed_origins: origins.map(|origins| origins.iter().map(|o| o.to_string()).collect()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn prefix_matches_whole_segments() {
+        assert!(matches_prefix("/api/users", "/api/users"));
+        assert!(matches_prefix("/api/users/1", "/api/users"));
+        assert!(matches_prefix("/api/users/1", "/api/users/"));
+        assert!(!matches_prefix("/api/users2", "/api/users"));
+        assert!(!matches_prefix("/api", "/api/users"));
+        assert!(matches_prefix("/anything", "/"));
+    }
+
+    #[test]
+    fn longest_prefix_wins() {
+        let routes = HashMap::from([
+            (String::from("/"), 1),
+            (String::from("/api"), 2),
+            (String::from("/api/users/"), 3),
+            (String::from("/api/users/export"), 4),
+        ]);
+        assert_eq!(longest_prefix(&routes, "/api/users/export/csv"), Some(4));
+        assert_eq!(longest_prefix(&routes, "/api/users"), Some(3));
+        assert_eq!(longest_prefix(&routes, "/api/users2"), Some(2));
+        assert_eq!(longest_prefix(&routes, "/auth/login"), Some(1));
+        assert_eq!(longest_prefix(&HashMap::<String, u64>::new(), "/api"), None);
+        // 尾部斜杠不影响长度比较，同样长度时结果稳定
+        let routes = HashMap::from([
+            (String::from("/api/users"), 1),
+            (String::from("/api/users/"), 2),
+        ]);
+        for _ in 0..10 {
+            assert_eq!(longest_prefix(&routes.clone(), "/api/users/1"), Some(2));
+        }
+    }
+
+    #[test]
+    fn cors_allows_listed_origins() {
+        let origin = |value| HeaderValue::from_static(value);
+        assert!(cors(None).allows_origin(&origin("http://a.example")));
+        assert!(cors(Some(&["*"])).allows_origin(&origin("http://a.example")));
+        let config = cors(Some(&["http://a.example"]));
+        assert!(config.allows_origin(&origin("http://a.example")));
+        assert!(!config.allows_origin(&origin("http://b.example")));
+        assert!(!config.allows_origin(&origin("http://a.example.evil")));
+    }
+}
@@ -1,40 +1,157 @@
-use std::sync::LazyLock;
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, OnceLock},
+};
 
 use anyhow::Context;
+use arc_swap::ArcSwap;
 use config::{Environment, File, FileFormat};
 use serde::Deserialize;
 
-use crate::config::{database::DatabaseConfig, server::ServerConfig};
+use crate::config::{
+    auth::AuthConfig, database::DatabaseConfig, logging::LoggingConfig, metrics::MetricsConfig,
+    server::ServerConfig, telemetry::TelemetryConfig, user::UserConfig,
+    validation::ConfigValidator,
+};
 
-mod database;
+pub(crate) mod auth;
+pub(crate) mod database;
+pub(crate) mod logging;
+mod metrics;
+pub(crate) mod middleware;
+pub(crate) mod rate_limit;
+mod reload;
+mod secret;
 pub(crate) mod server;
+pub(crate) mod telemetry;
+mod user;
+mod validation;
 
-static CONFIG: LazyLock<AppConfig> =
-    LazyLock::new(|| AppConfig::load().expect("Failed to load config"));
-#[derive(Debug, Deserialize)]
+pub(crate) use reload::{RELOAD_DEBOUNCE, watch_files};
+pub use reload::{on_reload, spawn_watcher};
+
+const DEFAULT_CONFIG_FILE: &str = "application";
+const ENV_PREFIX: &str = "APP";
+/// 环境变量嵌套分隔符，例如 APP_DATABASE__PASSWORD -> database.password
+const ENV_SEPARATOR: &str = "__";
+
+/// 启动时的配置快照，修改后需要重启才能生效的配置从这里读取
+static CONFIG: OnceLock<AppConfig> = OnceLock::new();
+/// 最新的配置，热加载时原子替换，可动态调整的配置从这里读取
+static CURRENT: OnceLock<ArcSwap<AppConfig>> = OnceLock::new();
+static OPTIONS: OnceLock<ConfigOptions> = OnceLock::new();
+
+/// 命令行传入的配置覆盖项
+#[derive(Debug, Clone, Default)]
+pub struct ConfigOptions {
+    /// 配置文件目录（默认当前目录）
+    pub dir: Option<PathBuf>,
+    /// 配置文件路径，优先于 dir（默认 {dir}/application.yaml）
+    pub file: Option<PathBuf>,
+    /// 激活的 profile，会额外加载 application-{profile}.yaml
+    pub profile: Option<String>,
+    /// 覆盖 server.port
+    pub port: Option<u16>,
+}
+
+impl ConfigOptions {
+    /// 基础配置和 profile 配置的路径
+    ///
+    /// 指定的配置文件原样使用，profile 配置为同目录下的 `{stem}-{profile}.{ext}`；
+    /// 默认为 `{dir}/application`（不含扩展名，按 yaml / yml 查找）
+    fn files(&self) -> (PathBuf, Option<PathBuf>) {
+        let file = self.file.clone().unwrap_or_else(|| {
+            self.dir
+                .as_deref()
+                .unwrap_or(Path::new("."))
+                .join(DEFAULT_CONFIG_FILE)
+        });
+        let profile_file = self
+            .profile
+            .as_ref()
+            .map(|profile| profile_file(&file, profile));
+        (file, profile_file)
+    }
+}
+
+fn profile_file(file: &Path, profile: &str) -> PathBuf {
+    let stem = file.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match file.extension() {
+        Some(extension) => format!("{stem}-{profile}.{}", extension.to_string_lossy()),
+        None => format!("{stem}-{profile}"),
+    };
+    file.with_file_name(name)
+}
+
+#[derive(Debug, Clone, Deserialize)]
 pub struct AppConfig {
     server: ServerConfig,
     database: DatabaseConfig,
+    #[serde(default)]
+    user: UserConfig,
+    #[serde(default)]
+    auth: AuthConfig,
+    #[serde(default)]
+    logging: LoggingConfig,
+    #[serde(default)]
+    metrics: MetricsConfig,
+    #[serde(default)]
+    telemetry: TelemetryConfig,
+    #[serde(skip)]
+    profile: Option<String>,
 }
 
 impl AppConfig {
-    pub fn load() -> anyhow::Result<Self> {
-        config::Config::builder()
-            .add_source(
-                File::with_name("application")
-                    .required(true)
+    /// 按 application.yaml -> application-{profile}.yaml -> 环境变量 -> 命令行 的顺序加载并校验
+    pub fn load(options: &ConfigOptions) -> anyhow::Result<Self> {
+        let (file, profile_file) = options.files();
+        let mut builder = config::Config::builder().add_source(
+            File::with_name(&file.to_string_lossy())
+                .required(true)
+                .format(FileFormat::Yaml),
+        );
+        if let Some(profile_file) = profile_file {
+            builder = builder.add_source(
+                File::with_name(&profile_file.to_string_lossy())
+                    .required(false)
                     .format(FileFormat::Yaml),
-            )
+            );
+        }
+        let mut config: AppConfig = builder
             .add_source(
-                Environment::with_prefix("APP")
-                    .try_parsing(true)
-                    .separator("_")
-                    .list_separator(","),
+                Environment::with_prefix(ENV_PREFIX)
+                    .prefix_separator("_")
+                    .separator(ENV_SEPARATOR)
+                    .try_parsing(true),
             )
+            .set_override_option("server.port", options.port)?
             .build()
             .with_context(|| anyhow::anyhow!("Failed to load config"))?
             .try_deserialize()
-            .with_context(|| anyhow::anyhow!("Failed to deserialize config"))
+            .with_context(|| anyhow::anyhow!("Failed to deserialize config"))?;
+        config.profile = options.profile.clone();
+        config.database.resolve_secrets()?;
+        config.auth.resolve_secrets()?;
+        config.validate()?;
+        Ok(config)
+    }
+    /// 校验全部配置项，一次性返回所有错误
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let mut validator = ConfigValidator::new(self.profile());
+        self.server.validate(&mut validator);
+        self.database.validate(&mut validator);
+        self.user.validate(&mut validator);
+        self.auth.validate(&mut validator);
+        self.logging.validate(&mut validator);
+        self.metrics.validate(&mut validator);
+        self.telemetry.validate(&mut validator);
+        validator.finish()
+    }
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+    pub fn is_prod(&self) -> bool {
+        validation::is_prod(self.profile())
     }
     pub fn server(&self) -> &ServerConfig {
         &self.server
@@ -42,8 +159,117 @@ impl AppConfig {
     pub fn database(&self) -> &DatabaseConfig {
         &self.database
     }
+    pub fn user(&self) -> &UserConfig {
+        &self.user
+    }
+    pub fn auth(&self) -> &AuthConfig {
+        &self.auth
+    }
+    pub fn logging(&self) -> &LoggingConfig {
+        &self.logging
+    }
+    pub fn metrics(&self) -> &MetricsConfig {
+        &self.metrics
+    }
+    pub fn telemetry(&self) -> &TelemetryConfig {
+        &self.telemetry
+    }
+}
+
+/// 使用命令行参数加载配置，需在第一次调用 [`get`] 之前执行
+pub fn init(options: &ConfigOptions) -> anyhow::Result<&'static AppConfig> {
+    let config = AppConfig::load(options)?;
+    CURRENT.get_or_init(|| ArcSwap::from_pointee(config.clone()));
+    OPTIONS.get_or_init(|| options.clone());
+    CONFIG
+        .set(config)
+        .map_err(|_| anyhow::anyhow!("Config has already been initialized"))?;
+    Ok(get())
+}
+
+/// 获取最新的配置（支持热加载），必须先调用 [`init`]
+pub fn current() -> Arc<AppConfig> {
+    CURRENT
+        .get()
+        .expect("Config is not initialized, call config::init first")
+        .load_full()
 }
 
+/// 获取启动时的配置，必须先调用 [`init`]
 pub fn get() -> &'static AppConfig {
-    &CONFIG
+    CONFIG
+        .get()
+        .expect("Config is not initialized, call config::init first")
+}
+
+/// 测试共享的配置，需要读取全局配置的测试（例如中间件）先调用
+#[cfg(test)]
+pub(crate) fn init_for_test() -> &'static AppConfig {
+    const TEST_CONFIG: &str = "
+server:
+  port: 3001
+  middleware:
+    rate_limit:
+      enabled: true
+      api_keys: [known-key]
+      routes:
+        - path: /limited
+          key: api_key
+          requests: 1
+          period: 60
+database:
+  host: localhost
+";
+    CONFIG.get_or_init(|| {
+        let config: AppConfig = config::Config::builder()
+            .add_source(File::from_str(TEST_CONFIG, FileFormat::Yaml))
+            .build()
+            .and_then(|config| config.try_deserialize())
+            .unwrap();
+        config.validate().unwrap();
+        CURRENT.get_or_init(|| ArcSwap::from_pointee(config.clone()));
+        config
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(file: Option<&str>, dir: Option<&str>, profile: Option<&str>) -> ConfigOptions {
+        ConfigOptions {
+            dir: dir.map(PathBuf::from),
+            file: file.map(PathBuf::from),
+            profile: profile.map(String::from),
+            port: None,
+        }
+    }
+
+    #[test]
+    fn explicit_file_is_used_as_is() {
+        let (file, profile_file) =
+            options(Some("/etc/app/my.config.yaml"), None, Some("prod")).files();
+        assert_eq!(file, PathBuf::from("/etc/app/my.config.yaml"));
+        assert_eq!(
+            profile_file,
+            Some(PathBuf::from("/etc/app/my.config-prod.yaml"))
+        );
+    }
+
+    #[test]
+    fn explicit_file_without_extension() {
+        let (file, profile_file) = options(Some("conf/app"), None, Some("dev")).files();
+        assert_eq!(file, PathBuf::from("conf/app"));
+        assert_eq!(profile_file, Some(PathBuf::from("conf/app-dev")));
+    }
+
+    #[test]
+    fn default_file_in_dir() {
+        let (file, profile_file) = options(None, Some("conf"), Some("test")).files();
+        assert_eq!(file, PathBuf::from("conf/application"));
+        assert_eq!(profile_file, Some(PathBuf::from("conf/application-test")));
+        let (file, profile_file) = options(None, None, None).files();
+        assert_eq!(file, PathBuf::from("./application"));
+        assert_eq!(profile_file, None);
+    }
 }
@@ -0,0 +1,207 @@
+use std::{str::FromStr, time::Duration};
+
+use axum::http::{HeaderName, Method};
+use serde::Deserialize;
+
+use crate::{
+    config::{middleware::matches_prefix, secret::Secret, validation::ConfigValidator},
+    utils::crypt::constant_time_eq,
+};
+
+/// 限流维度
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitKey {
+    /// 客户端 IP
+    #[default]
+    Ip,
+    /// 登录用户 id，未登录时按 IP
+    User,
+    /// API key 请求头，未携带或不在 `api_keys` 中时按 IP
+    ApiKey,
+}
+
+/// 限流配置，`routes` 按路径前缀（最长前缀优先）和方法匹配，未匹配的请求使用 `default`
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct RateLimitConfig {
+    enabled: Option<bool>,
+    api_key_header: Option<String>,
+    /// 已知的 API key，未知的 key 按 IP 限流，避免每次换一个 key 绕过限流
+    #[serde(default)]
+    api_keys: Vec<Secret<String>>,
+    default: Option<QuotaConfig>,
+    #[serde(default)]
+    routes: Vec<RouteQuotaConfig>,
+}
+
+/// 每 `period` 秒允许 `requests` 个请求，`burst` 为允许的突发请求数（默认等于 requests）
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct QuotaConfig {
+    #[serde(default)]
+    key: RateLimitKey,
+    requests: u32,
+    period: Option<u64>,
+    burst: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RouteQuotaConfig {
+    path: String,
+    /// 为空时匹配所有方法
+    #[serde(default)]
+    methods: Vec<String>,
+    #[serde(default)]
+    key: RateLimitKey,
+    requests: u32,
+    period: Option<u64>,
+    burst: Option<u32>,
+}
+
+/// 解析后的配额
+#[derive(Debug, Clone)]
+pub struct Quota {
+    /// 配额名称，作为限流存储 key 的前缀
+    pub name: String,
+    pub key: RateLimitKey,
+    pub limit: u32,
+    pub period: Duration,
+    pub burst: u32,
+}
+
+impl Quota {
+    fn new(
+        name: String,
+        key: RateLimitKey,
+        requests: u32,
+        period: Option<u64>,
+        burst: Option<u32>,
+    ) -> Self {
+        Self {
+            name,
+            key,
+            limit: requests,
+            period: Duration::from_secs(period.unwrap_or(60)),
+            burst: burst.unwrap_or(requests),
+        }
+    }
+}
+
+impl RateLimitConfig {
+    pub fn enabled(&self) -> bool {
+        self.enabled.unwrap_or(false)
+    }
+    pub fn api_key_header(&self) -> &str {
+        self.api_key_header.as_deref().unwrap_or("x-api-key")
+    }
+    pub fn is_known_api_key(&self, api_key: &str) -> bool {
+        self.api_keys
+            .iter()
+            .any(|known| constant_time_eq(known.expose().as_bytes(), api_key.as_bytes()))
+    }
+    /// 请求对应的配额，没有配额时不限流
+    pub fn quota_for(&self, method: &Method, path: &str) -> Option<Quota> {
+        let route = self
+            .routes
+            .iter()
+            .filter(|route| matches_prefix(path, &route.path))
+            .filter(|route| {
+                route.methods.is_empty()
+                    || route
+                        .methods
+                        .iter()
+                        .any(|allowed| allowed.eq_ignore_ascii_case(method.as_str()))
+            })
+            .max_by_key(|route| route.path.trim_end_matches('/').len());
+        match route {
+            Some(route) => Some(Quota::new(
+                route.path.clone(),
+                route.key,
+                route.requests,
+                route.period,
+                route.burst,
+            )),
+            None => self.default.as_ref().map(|quota| {
+                Quota::new(
+                    String::from("default"),
+                    quota.key,
+                    quota.requests,
+                    quota.period,
+                    quota.burst,
+                )
+            }),
+        }
+    }
+    pub(super) fn validate(&self, validator: &mut ConfigValidator) {
+        validator.check(
+            HeaderName::from_str(self.api_key_header()).is_ok(),
+            "server.middleware.rate_limit.api_key_header",
+            "must be a valid header name",
+        );
+        let uses_api_key = self
+            .default
+            .as_ref()
+            .map(|quota| quota.key)
+            .into_iter()
+            .chain(self.routes.iter().map(|route| route.key))
+            .any(|key| key == RateLimitKey::ApiKey);
+        validator.check(
+            !uses_api_key || !self.api_keys.is_empty(),
+            "server.middleware.rate_limit.api_keys",
+            "must not be empty when a quota is keyed by api_key",
+        );
+        validator.check(
+            self.api_keys.iter().all(|key| !key.expose().is_empty()),
+            "server.middleware.rate_limit.api_keys",
+            "must not contain empty keys",
+        );
+        if let Some(quota) = &self.default {
+            check_quota(
+                validator,
+                "server.middleware.rate_limit.default",
+                quota.requests,
+                quota.period,
+                quota.burst,
+            );
+        }
+        for (index, route) in self.routes.iter().enumerate() {
+            let key = format!("server.middleware.rate_limit.routes[{index}]");
+            validator.check(
+                route.path.starts_with('/'),
+                &format!("{key}.path"),
+                "must start with /",
+            );
+            for method in &route.methods {
+                validator.check(
+                    Method::from_str(&method.to_uppercase()).is_ok(),
+                    &format!("{key}.methods"),
+                    format!("invalid method {method}"),
+                );
+            }
+            check_quota(validator, &key, route.requests, route.period, route.burst);
+        }
+    }
+}
+
+fn check_quota(
+    validator: &mut ConfigValidator,
+    key: &str,
+    requests: u32,
+    period: Option<u64>,
+    burst: Option<u32>,
+) {
+    validator.check(
+        requests > 0,
+        &format!("{key}.requests"),
+        "must be greater than 0",
+    );
+    validator.check(
+        period != Some(0),
+        &format!("{key}.period"),
+        "must be greater than 0",
+    );
+    validator.check(
+        burst != Some(0),
+        &format!("{key}.burst"),
+        "must be greater than 0",
+    );
+}
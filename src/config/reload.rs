@@ -0,0 +1,293 @@
+use std::{
+    collections::HashSet,
+    ffi::OsString,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+use anyhow::Context;
+use notify::{Event, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+
+use crate::config::{AppConfig, CURRENT, OPTIONS};
+
+type ReloadListener = Box<dyn Fn(&AppConfig) + Send + Sync>;
+
+/// 文件变化后等待一段时间再加载，合并编辑器保存时的多次事件，避免读到只写了一半的文件
+pub(crate) const RELOAD_DEBOUNCE: Duration = Duration::from_millis(500);
+
+static LISTENERS: Mutex<Vec<ReloadListener>> = Mutex::new(Vec::new());
+
+/// 注册配置热加载回调，各子系统在回调中应用可动态调整的配置
+pub fn on_reload<F>(listener: F)
+where
+    F: Fn(&AppConfig) + Send + Sync + 'static,
+{
+    LISTENERS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(Box::new(listener));
+}
+
+/// 重新加载并校验配置，校验失败时保留当前配置
+pub fn reload() -> anyhow::Result<()> {
+    let options = OPTIONS.get().context("Config is not initialized")?;
+    let config = Arc::new(AppConfig::load(options)?);
+    for key in restart_required_changes(super::get(), &config) {
+        tracing::warn!(
+            "Config in `{}` changed, restart required to take effect",
+            key
+        );
+    }
+    CURRENT
+        .get()
+        .context("Config is not initialized")?
+        .store(config.clone());
+    for listener in LISTENERS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+    {
+        listener(&config);
+    }
+    tracing::info!("Configuration reloaded");
+    Ok(())
+}
+
+/// 监听配置文件变化和 SIGHUP 信号，触发热加载
+pub fn spawn_watcher() -> anyhow::Result<()> {
+    if super::get().server().watch_config() {
+        let (file, profile_file) = OPTIONS.get().context("Config is not initialized")?.files();
+        // 不含扩展名的默认配置按 application.yaml / application.yml 监听
+        let paths = [Some(file), profile_file]
+            .into_iter()
+            .flatten()
+            .flat_map(|file| match file.extension() {
+                Some(_) => vec![file],
+                None => vec![file.with_extension("yaml"), file.with_extension("yml")],
+            })
+            .collect();
+        watch_files(paths, RELOAD_DEBOUNCE, reload_or_log)?;
+    }
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+        let mut hangup = signal(SignalKind::hangup())?;
+        tokio::spawn(async move {
+            while hangup.recv().await.is_some() {
+                tracing::info!("Received SIGHUP, reloading configuration");
+                reload_or_log();
+            }
+        });
+    }
+    Ok(())
+}
+
+fn reload_or_log() {
+    if let Err(err) = reload() {
+        tracing::error!("Failed to reload configuration: {:#}", err);
+    }
+}
+
+/// 监听文件变化，等待 `debounce` 合并多次事件后调用 `callback`
+///
+/// 监听文件所在目录而不是文件本身，兼容编辑器先删除再创建的保存方式；
+/// 除按文件名匹配外还比较软链接解析后的目标，Kubernetes 挂载的 ConfigMap / Secret
+/// 通过替换 `..data` 软链接整体更新，事件中不会出现文件名本身
+pub(crate) fn watch_files<F>(
+    paths: Vec<PathBuf>,
+    debounce: Duration,
+    callback: F,
+) -> anyhow::Result<()>
+where
+    F: Fn() + Send + 'static,
+{
+    let (tx, mut rx) = mpsc::channel::<()>(1);
+    let file_names = paths
+        .iter()
+        .filter_map(|path| path.file_name().map(OsString::from))
+        .collect::<Vec<_>>();
+    let resolve = {
+        let paths = paths.clone();
+        move || {
+            paths
+                .iter()
+                .map(|path| std::fs::canonicalize(path).ok())
+                .collect::<Vec<_>>()
+        }
+    };
+    let mut targets = resolve();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+        if event.kind.is_access() {
+            return;
+        }
+        let resolved = resolve();
+        let changed = resolved != targets
+            || event.paths.iter().any(|path| {
+                path.file_name()
+                    .is_some_and(|name| file_names.iter().any(|file_name| file_name == name))
+            });
+        targets = resolved;
+        if changed {
+            let _ = tx.try_send(());
+        }
+    })?;
+    let dirs = paths
+        .iter()
+        .map(|path| {
+            path.parent()
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .unwrap_or(Path::new("."))
+        })
+        .collect::<HashSet<_>>();
+    for dir in dirs {
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+        tracing::info!("Watching files in {}", dir.display());
+    }
+    tokio::spawn(async move {
+        // watcher 被 drop 后会停止监听
+        let _watcher = watcher;
+        while rx.recv().await.is_some() {
+            tokio::time::sleep(debounce).await;
+            while rx.try_recv().is_ok() {}
+            callback();
+        }
+    });
+    Ok(())
+}
+
+/// 只在启动时读取的配置，变化后需要重启服务
+///
+/// 各配置段通过派生的 `PartialEq` 比较，只排除明确热加载生效的字段，新增的字段默认需要重启；
+/// user 配置全部热加载生效
+fn restart_required_changes(old: &AppConfig, new: &AppConfig) -> Vec<&'static str> {
+    [
+        ("server", old.server().requires_restart(new.server())),
+        (
+            "server.middleware",
+            old.server()
+                .middleware()
+                .requires_restart(new.server().middleware()),
+        ),
+        ("database", old.database().requires_restart(new.database())),
+        ("auth", old.auth().requires_restart(new.auth())),
+        ("logging", old.logging().requires_restart(new.logging())),
+        ("metrics", old.metrics().requires_restart(new.metrics())),
+        ("telemetry", old.telemetry() != new.telemetry()),
+    ]
+    .into_iter()
+    .filter_map(|(key, changed)| changed.then_some(key))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = "server:\n  port: 3001\ndatabase:\n  host: db\n";
+
+    fn changes(overrides: &str) -> Vec<&'static str> {
+        let load = |overrides: &str| -> AppConfig {
+            config::Config::builder()
+                .add_source(config::File::from_str(BASE, config::FileFormat::Yaml))
+                .add_source(config::File::from_str(overrides, config::FileFormat::Yaml))
+                .build()
+                .and_then(|config| config.try_deserialize())
+                .unwrap()
+        };
+        restart_required_changes(&load(""), &load(overrides))
+    }
+
+    #[test]
+    fn hot_reloaded_fields_do_not_require_restart() {
+        for overrides in [
+            "server:\n  health_check_timeout: 1",
+            "server:\n  trusted_proxies: [10.0.0.0/8]",
+            "server:\n  middleware:\n    timeout:\n      duration: 5\n      routes:\n        /api: 10",
+            "server:\n  middleware:\n    cors:\n      allowed_origins: [http://a.example]",
+            "server:\n  middleware:\n    rate_limit:\n      enabled: true",
+            "server:\n  middleware:\n    request_id:\n      header: x-trace-id",
+            "server:\n  middleware:\n    body_log:\n      enabled: true",
+            "server:\n  middleware:\n    slow_request:\n      threshold: 10",
+            "database:\n  slow_query:\n    threshold: 10",
+            "user:\n  batch_max_size: 10",
+            "auth:\n  jwt_expiration: 10\n  admin_accounts: [root]",
+            "logging:\n  level: debug\n  targets:\n    sqlx: info\n  redact:\n    enabled: false",
+            "metrics:\n  token: s3cret",
+        ] {
+            assert!(changes(overrides).is_empty(), "{overrides}");
+        }
+    }
+
+    #[test]
+    fn startup_fields_require_restart() {
+        for (overrides, section) in [
+            ("server:\n  host: 127.0.0.1", "server"),
+            ("server:\n  port: 3002", "server"),
+            ("server:\n  watch_config: false", "server"),
+            ("server:\n  shutdown_timeout: 1", "server"),
+            ("server:\n  keep_alive: false", "server"),
+            ("server:\n  http2:\n    enabled: false", "server"),
+            (
+                "server:\n  tls:\n    cert_file: a.crt\n    key_file: a.key",
+                "server",
+            ),
+            (
+                "server:\n  middleware:\n    timeout:\n      enabled: false",
+                "server.middleware",
+            ),
+            (
+                "server:\n  middleware:\n    body_limit:\n      max_size: 1MiB",
+                "server.middleware",
+            ),
+            (
+                "server:\n  middleware:\n    cors:\n      allow_credentials: true",
+                "server.middleware",
+            ),
+            (
+                "server:\n  middleware:\n    trace: false",
+                "server.middleware",
+            ),
+            ("database:\n  port: 6543", "database"),
+            ("database:\n  max_connections: 5", "database"),
+            ("auth:\n  jwt_issuer: other", "auth"),
+            ("logging:\n  format: json", "logging"),
+            ("metrics:\n  path: /internal/metrics", "metrics"),
+            ("telemetry:\n  sample_ratio: 0.5", "telemetry"),
+        ] {
+            assert_eq!(changes(overrides), [section], "{overrides}");
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn watch_files_follows_symlink_swap() {
+        use std::os::unix::fs::symlink;
+
+        let dir = std::env::temp_dir().join(format!("watch-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        // 按 Kubernetes 挂载 Secret 的结构：tls.crt -> ..data/tls.crt，..data -> ..v1
+        for version in ["..v1", "..v2"] {
+            std::fs::create_dir_all(dir.join(version)).unwrap();
+            std::fs::write(dir.join(version).join("tls.crt"), version).unwrap();
+        }
+        symlink("..v1", dir.join("..data")).unwrap();
+        symlink("..data/tls.crt", dir.join("tls.crt")).unwrap();
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        watch_files(vec![dir.join("tls.crt")], Duration::ZERO, move || {
+            let _ = tx.send(());
+        })
+        .unwrap();
+        symlink("..v2", dir.join("..data_tmp")).unwrap();
+        std::fs::rename(dir.join("..data_tmp"), dir.join("..data")).unwrap();
+
+        let reloaded = tokio::time::timeout(Duration::from_secs(5), rx.recv()).await;
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(reloaded, Ok(Some(()))));
+    }
+}
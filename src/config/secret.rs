@@ -0,0 +1,125 @@
+use std::{
+    fmt::{Debug, Display},
+    path::Path,
+};
+
+use anyhow::Context;
+use serde::{Deserialize, Deserializer};
+use zeroize::Zeroize;
+
+const REDACTED: &str = "******";
+
+/// 敏感配置项：Debug/Display 输出脱敏，drop 时清零内存
+///
+/// 反序列化时支持引用写法：
+/// - `${env:VAR}` 读取环境变量 VAR
+/// - `${file:/path/to/secret}` 读取文件内容（去掉末尾换行）
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Secret<T: Zeroize>(T);
+
+impl<T: Zeroize> Secret<T> {
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+}
+
+impl Secret<String> {
+    /// 读取 secret 文件，去掉末尾换行
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let mut content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read secret file {}", path.display()))?;
+        let trimmed_len = content.trim_end_matches(['\r', '\n']).len();
+        content.truncate(trimmed_len);
+        Ok(Self(content))
+    }
+    /// 解析 `${env:VAR}` / `${file:/path}` 引用，其余按字面值处理
+    pub fn resolve(value: String) -> anyhow::Result<Self> {
+        let Some(reference) = value
+            .strip_prefix("${")
+            .and_then(|value| value.strip_suffix('}'))
+        else {
+            return Ok(Self(value));
+        };
+        match reference.split_once(':') {
+            Some(("env", name)) => std::env::var(name)
+                .map(Self)
+                .with_context(|| format!("Failed to read secret from env {name}")),
+            Some(("file", path)) => Self::from_file(path),
+            _ => anyhow::bail!(
+                "Unsupported secret reference, expected ${{env:VAR}} or ${{file:PATH}}"
+            ),
+        }
+    }
+}
+
+impl<T: Zeroize> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl<T: Zeroize> Debug for Secret<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<T: Zeroize> Display for Secret<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<'de> Deserialize<'de> for Secret<String> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        Secret::resolve(value).map_err(|err| serde::de::Error::custom(format!("{err:#}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal_value_is_kept() {
+        let secret = Secret::resolve(String::from("plain-secret")).unwrap();
+        assert_eq!(secret.expose(), "plain-secret");
+        let secret = Secret::resolve(String::from("${not closed")).unwrap();
+        assert_eq!(secret.expose(), "${not closed");
+    }
+
+    #[test]
+    fn resolves_env_reference() {
+        let path = std::env::var("PATH").unwrap();
+        let secret = Secret::resolve(String::from("${env:PATH}")).unwrap();
+        assert_eq!(secret.expose(), &path);
+        assert!(Secret::resolve(String::from("${env:APP_TEST_MISSING_SECRET_VAR}")).is_err());
+    }
+
+    #[test]
+    fn resolves_file_reference_without_trailing_newline() {
+        let path = std::env::temp_dir().join(format!("secret-test-{}", std::process::id()));
+        std::fs::write(&path, "s3cret\r\n").unwrap();
+        let secret = Secret::resolve(format!("${{file:{}}}", path.display())).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(secret.expose(), "s3cret");
+        assert!(Secret::resolve(String::from("${file:/nonexistent/secret}")).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_reference() {
+        assert!(Secret::resolve(String::from("${vault:db/password}")).is_err());
+    }
+
+    #[test]
+    fn debug_and_display_are_redacted() {
+        let secret = Secret::resolve(String::from("plain-secret")).unwrap();
+        assert_eq!(format!("{secret:?}"), REDACTED);
+        assert_eq!(secret.to_string(), REDACTED);
+    }
+}
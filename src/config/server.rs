@@ -1,12 +1,243 @@
-use serde::Deserialize;
+use std::{net::IpAddr, path::PathBuf, time::Duration};
 
-#[derive(Debug, Deserialize)]
+use ipnet::IpNet;
+use serde::{Deserialize, Deserializer};
+
+use crate::config::{middleware::MiddlewareConfig, validation::ConfigValidator};
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct ServerConfig {
+    /// 监听地址，IPv6 使用 `::`
+    host: Option<String>,
     port: Option<u16>,
+    /// Unix domain socket 路径，设置后不再监听 host/port
+    unix_socket: Option<PathBuf>,
+    watch_config: Option<bool>,
+    health_check_timeout: Option<u64>,
+    shutdown_delay: Option<u64>,
+    shutdown_timeout: Option<u64>,
+    keep_alive: Option<bool>,
+    header_read_timeout: Option<u64>,
+    /// 可信代理的 IP 或 CIDR，只有来自这些地址的请求才读取转发头中的客户端 IP
+    #[serde(default, deserialize_with = "deserialize_ip_nets")]
+    trusted_proxies: Vec<IpNet>,
+    tls: Option<TlsConfig>,
+    #[serde(default)]
+    http2: Http2Config,
+    #[serde(default)]
+    middleware: MiddlewareConfig,
+}
+
+/// HTTPS 证书配置，证书和私钥均为 PEM 格式
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TlsConfig {
+    cert_file: PathBuf,
+    key_file: PathBuf,
+    watch: Option<bool>,
+}
+
+impl TlsConfig {
+    pub fn cert_file(&self) -> &PathBuf {
+        &self.cert_file
+    }
+    pub fn key_file(&self) -> &PathBuf {
+        &self.key_file
+    }
+    /// 证书文件变化时自动重新加载，便于证书续期
+    pub fn watch(&self) -> bool {
+        self.watch.unwrap_or(true)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Http2Config {
+    enabled: Option<bool>,
+    max_concurrent_streams: Option<u32>,
+    keep_alive_interval: Option<u64>,
+    keep_alive_timeout: Option<u64>,
+}
+
+impl Http2Config {
+    /// 关闭后只支持 HTTP/1.1
+    pub fn enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+    pub fn max_concurrent_streams(&self) -> u32 {
+        self.max_concurrent_streams.unwrap_or(200)
+    }
+    /// 发送 PING 帧的间隔（秒），不设置时不发送
+    pub fn keep_alive_interval(&self) -> Option<Duration> {
+        self.keep_alive_interval.map(Duration::from_secs)
+    }
+    /// 等待 PING 响应的超时时间（秒）
+    pub fn keep_alive_timeout(&self) -> Duration {
+        Duration::from_secs(self.keep_alive_timeout.unwrap_or(20))
+    }
 }
 
 impl ServerConfig {
+    /// 除热加载生效的 health_check_timeout、trusted_proxies 外是否有变化，middleware 单独比较
+    pub(super) fn requires_restart(&self, other: &Self) -> bool {
+        *self
+            != Self {
+                health_check_timeout: self.health_check_timeout,
+                trusted_proxies: self.trusted_proxies.clone(),
+                middleware: self.middleware.clone(),
+                ..other.clone()
+            }
+    }
+    pub fn host(&self) -> &str {
+        self.host.as_deref().unwrap_or("0.0.0.0")
+    }
     pub fn port(&self) -> u16 {
         self.port.unwrap_or(3000)
     }
+    pub fn unix_socket(&self) -> Option<&PathBuf> {
+        self.unix_socket.as_ref()
+    }
+    /// 是否监听配置文件变化自动热加载（SIGHUP 始终可用）
+    pub fn watch_config(&self) -> bool {
+        self.watch_config.unwrap_or(true)
+    }
+    /// 就绪检查中单项检查的超时时间（毫秒）
+    pub fn health_check_timeout(&self) -> Duration {
+        Duration::from_millis(self.health_check_timeout.unwrap_or(3000))
+    }
+    /// 收到停止信号后先标记为未就绪，等待该时间（秒）再停止接收新连接，留给负载均衡摘除流量
+    pub fn shutdown_delay(&self) -> Duration {
+        Duration::from_secs(self.shutdown_delay.unwrap_or(0))
+    }
+    /// 停止接收新连接后等待处理中请求完成的最长时间（秒），超时后强制关闭
+    pub fn shutdown_timeout(&self) -> Duration {
+        Duration::from_secs(self.shutdown_timeout.unwrap_or(30))
+    }
+    /// HTTP/1.1 是否保持连接
+    pub fn keep_alive(&self) -> bool {
+        self.keep_alive.unwrap_or(true)
+    }
+    /// 读取 HTTP/1.1 请求头的超时时间（秒）
+    pub fn header_read_timeout(&self) -> Duration {
+        Duration::from_secs(self.header_read_timeout.unwrap_or(30))
+    }
+    /// 可信代理网段，单个 IP 视为 /32 或 /128
+    pub fn trusted_proxies(&self) -> &[IpNet] {
+        &self.trusted_proxies
+    }
+    pub fn tls(&self) -> Option<&TlsConfig> {
+        self.tls.as_ref()
+    }
+    pub fn http2(&self) -> &Http2Config {
+        &self.http2
+    }
+    pub fn middleware(&self) -> &MiddlewareConfig {
+        &self.middleware
+    }
+    pub(super) fn validate(&self, validator: &mut ConfigValidator) {
+        validator.check(
+            self.port() != 0,
+            "server.port",
+            "must be between 1 and 65535",
+        );
+        validator.check(
+            self.host().parse::<IpAddr>().is_ok(),
+            "server.host",
+            "must be an IPv4 or IPv6 address",
+        );
+        validator.check(
+            self.unix_socket.is_none() || cfg!(unix),
+            "server.unix_socket",
+            "is only supported on unix",
+        );
+        validator.check(
+            !self.health_check_timeout().is_zero(),
+            "server.health_check_timeout",
+            "must be greater than 0",
+        );
+        validator.check(
+            !self.header_read_timeout().is_zero(),
+            "server.header_read_timeout",
+            "must be greater than 0",
+        );
+        validator.check(
+            self.http2.max_concurrent_streams() > 0,
+            "server.http2.max_concurrent_streams",
+            "must be greater than 0",
+        );
+        self.middleware.validate(validator);
+        if let Some(tls) = &self.tls {
+            validator.check(
+                tls.cert_file.is_file(),
+                "server.tls.cert_file",
+                format!("file {} does not exist", tls.cert_file.display()),
+            );
+            validator.check(
+                tls.key_file.is_file(),
+                "server.tls.key_file",
+                format!("file {} does not exist", tls.key_file.display()),
+            );
+        }
+    }
+}
+
+/// 加载配置时解析一次，无法解析的值导致加载失败（热加载时保留当前配置）
+fn deserialize_ip_nets<'de, D>(deserializer: D) -> Result<Vec<IpNet>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|value| {
+            parse_ip_net(value).ok_or_else(|| {
+                serde::de::Error::custom(format!("invalid IP or CIDR {value} in trusted_proxies"))
+            })
+        })
+        .collect()
+}
+
+fn parse_ip_net(value: &str) -> Option<IpNet> {
+    value
+        .parse::<IpNet>()
+        .ok()
+        .or_else(|| value.parse::<IpAddr>().ok().map(IpNet::from))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_ip_net_accepts_ip_and_cidr() {
+        let net = parse_ip_net("10.0.0.0/8").unwrap();
+        assert!(net.contains(&"10.255.0.1".parse::<IpAddr>().unwrap()));
+        assert!(!net.contains(&"11.0.0.1".parse::<IpAddr>().unwrap()));
+
+        let net = parse_ip_net("127.0.0.1").unwrap();
+        assert!(net.contains(&"127.0.0.1".parse::<IpAddr>().unwrap()));
+        assert!(!net.contains(&"127.0.0.2".parse::<IpAddr>().unwrap()));
+
+        let net = parse_ip_net("fd00::/8").unwrap();
+        assert!(net.contains(&"fd12::1".parse::<IpAddr>().unwrap()));
+
+        assert_eq!(parse_ip_net("not-an-ip"), None);
+        assert_eq!(parse_ip_net("10.0.0.0/33"), None);
+    }
+
+    #[test]
+    fn trusted_proxies_are_parsed_on_load() {
+        let load = |yaml: &str| {
+            config::Config::builder()
+                .add_source(config::File::from_str(yaml, config::FileFormat::Yaml))
+                .build()
+                .and_then(|config| config.try_deserialize::<ServerConfig>())
+        };
+        let config = load("trusted_proxies: [10.0.0.0/8, 127.0.0.1]").unwrap();
+        assert_eq!(
+            config.trusted_proxies(),
+            [
+                "10.0.0.0/8".parse().unwrap(),
+                "127.0.0.1/32".parse().unwrap()
+            ]
+        );
+        assert!(load("trusted_proxies: [10.0.0.0/8, not-an-ip]").is_err());
+    }
 }
@@ -0,0 +1,65 @@
+use std::{collections::HashMap, time::Duration};
+
+use serde::Deserialize;
+
+use crate::config::{secret::Secret, validation::ConfigValidator};
+
+/// OpenTelemetry 链路追踪，通过 OTLP/HTTP (protobuf) 导出，修改后需要重启
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct TelemetryConfig {
+    enabled: Option<bool>,
+    endpoint: Option<String>,
+    service_name: Option<String>,
+    sample_ratio: Option<f64>,
+    timeout: Option<u64>,
+    #[serde(default)]
+    headers: HashMap<String, Secret<String>>,
+}
+
+impl TelemetryConfig {
+    pub fn enabled(&self) -> bool {
+        self.enabled.unwrap_or(false)
+    }
+    /// OTLP traces 接收地址
+    pub fn endpoint(&self) -> &str {
+        self.endpoint
+            .as_deref()
+            .unwrap_or("http://localhost:4318/v1/traces")
+    }
+    pub fn service_name(&self) -> &str {
+        self.service_name.as_deref().unwrap_or("axum-starter")
+    }
+    /// 采样比例 0.0 ~ 1.0，上游已经决定采样时跟随上游
+    pub fn sample_ratio(&self) -> f64 {
+        self.sample_ratio.unwrap_or(1.0)
+    }
+    /// 导出超时时间（秒）
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout.unwrap_or(10))
+    }
+    /// 导出时附加的请求头，例如认证信息，值支持 `${env:VAR}` / `${file:/path}` 引用
+    pub fn headers(&self) -> &HashMap<String, Secret<String>> {
+        &self.headers
+    }
+    pub(super) fn validate(&self, validator: &mut ConfigValidator) {
+        if !self.enabled() {
+            return;
+        }
+        validator.check(
+            url::Url::parse(self.endpoint())
+                .is_ok_and(|url| matches!(url.scheme(), "http" | "https")),
+            "telemetry.endpoint",
+            "must be an http(s) url",
+        );
+        validator.check(
+            (0.0..=1.0).contains(&self.sample_ratio()),
+            "telemetry.sample_ratio",
+            "must be between 0.0 and 1.0",
+        );
+        validator.check(
+            !self.timeout().is_zero(),
+            "telemetry.timeout",
+            "must be greater than 0",
+        );
+    }
+}
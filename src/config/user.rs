@@ -0,0 +1,31 @@
+use serde::Deserialize;
+
+use crate::config::validation::ConfigValidator;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UserConfig {
+    batch_max_size: Option<usize>,
+    xlsx_export_max_rows: Option<u64>,
+}
+
+impl UserConfig {
+    pub fn batch_max_size(&self) -> usize {
+        self.batch_max_size.unwrap_or(100)
+    }
+    /// xlsx 导出需要在内存中生成完整文件，超过该行数时拒绝导出（csv 为流式导出，不受限制）
+    pub fn xlsx_export_max_rows(&self) -> u64 {
+        self.xlsx_export_max_rows.unwrap_or(10000)
+    }
+    pub(super) fn validate(&self, validator: &mut ConfigValidator) {
+        validator.check(
+            (1..=10000).contains(&self.batch_max_size()),
+            "user.batch_max_size",
+            "must be between 1 and 10000",
+        );
+        validator.check(
+            (1..=1_000_000).contains(&self.xlsx_export_max_rows()),
+            "user.xlsx_export_max_rows",
+            "must be between 1 and 1000000",
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use std::fmt::Display;
+
+/// 生产环境 profile：prod / production
+pub fn is_prod(profile: Option<&str>) -> bool {
+    matches!(profile, Some("prod" | "production"))
+}
+
+/// 收集所有配置错误，一次性报告
+#[derive(Debug, Default)]
+pub struct ConfigValidator {
+    profile: Option<String>,
+    errors: Vec<String>,
+}
+
+impl ConfigValidator {
+    pub fn new(profile: Option<&str>) -> Self {
+        Self {
+            profile: profile.map(String::from),
+            errors: Vec::new(),
+        }
+    }
+    pub fn is_prod(&self) -> bool {
+        is_prod(self.profile.as_deref())
+    }
+    pub fn check(&mut self, valid: bool, key: &str, message: impl Display) {
+        if !valid {
+            self.errors.push(format!("{key}: {message}"));
+        }
+    }
+    pub fn finish(self) -> anyhow::Result<()> {
+        if self.errors.is_empty() {
+            return Ok(());
+        }
+        anyhow::bail!(
+            "Invalid configuration ({} errors):\n  - {}",
+            self.errors.len(),
+            self.errors.join("\n  - ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_all_errors_at_once() {
+        let mut validator = ConfigValidator::new(None);
+        validator.check(true, "server.port", "ok");
+        validator.check(false, "server.host", "must not be empty");
+        validator.check(false, "database.port", "must be greater than 0");
+        let err = validator.finish().unwrap_err().to_string();
+        assert!(err.starts_with("Invalid configuration (2 errors)"));
+        assert!(err.contains("server.host: must not be empty"));
+        assert!(err.contains("database.port: must be greater than 0"));
+        assert!(!err.contains("server.port"));
+    }
+
+    #[test]
+    fn passes_without_errors() {
+        let mut validator = ConfigValidator::new(Some("dev"));
+        validator.check(true, "server.port", "ok");
+        assert!(validator.finish().is_ok());
+    }
+
+    #[test]
+    fn detects_prod_profile() {
+        assert!(ConfigValidator::new(Some("prod")).is_prod());
+        assert!(ConfigValidator::new(Some("production")).is_prod());
+        assert!(!ConfigValidator::new(Some("dev")).is_prod());
+        assert!(!ConfigValidator::new(None).is_prod());
+    }
+}
@@ -1,10 +1,14 @@
+use clap::Parser;
+
 mod api;
 mod app;
+mod cli;
 mod config;
 mod entity;
+mod migration;
 mod utils;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    app::run(api::create_router()).await
+    cli::Cli::parse().run().await
 }
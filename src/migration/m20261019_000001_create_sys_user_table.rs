@@ -0,0 +1,79 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(SysUser::Table)
+                    .if_not_exists()
+                    .col(string(SysUser::Id).primary_key())
+                    .col(string(SysUser::Username))
+                    .col(
+                        string(SysUser::Gender)
+                            .check(Expr::col(SysUser::Gender).is_in(["male", "female"])),
+                    )
+                    .col(string(SysUser::Account))
+                    .col(string(SysUser::Password))
+                    .col(string(SysUser::MobilePhone))
+                    .col(date(SysUser::Birthday))
+                    .col(boolean(SysUser::Enbaled).default(true))
+                    .col(date_time(SysUser::CreatedDate).default(Expr::current_timestamp()))
+                    .col(date_time(SysUser::UpdatedDate).default(Expr::current_timestamp()))
+                    .col(string(SysUser::CreatedBy).default("system"))
+                    .col(string_null(SysUser::UpdatedBy))
+                    .to_owned(),
+            )
+            .await?;
+        // 唯一索引名与 ApiError 中的冲突映射保持一致
+        manager
+            .create_index(
+                Index::create()
+                    .name("uk_sys_user_account")
+                    .table(SysUser::Table)
+                    .col(SysUser::Account)
+                    .unique()
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("uk_sys_user_mobile_phone")
+                    .table(SysUser::Table)
+                    .col(SysUser::MobilePhone)
+                    .unique()
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(SysUser::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum SysUser {
+    Table,
+    Id,
+    Username,
+    Gender,
+    Account,
+    Password,
+    MobilePhone,
+    Birthday,
+    Enbaled,
+    CreatedDate,
+    UpdatedDate,
+    CreatedBy,
+    UpdatedBy,
+}
@@ -0,0 +1,57 @@
+use clap::Subcommand;
+use sea_orm::DatabaseConnection;
+pub use sea_orm_migration::MigratorTrait;
+use sea_orm_migration::{MigrationTrait, async_trait};
+
+mod m20261019_000001_create_sys_user_table;
+
+pub struct Migrator;
+
+#[async_trait::async_trait]
+impl MigratorTrait for Migrator {
+    fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+        // 新的迁移按时间顺序追加到末尾
+        vec![Box::new(m20261019_000001_create_sys_user_table::Migration)]
+    }
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum MigrateCommand {
+    /// Apply pending migrations
+    Up {
+        /// Number of pending migrations to apply (default: all)
+        #[arg(short, long)]
+        steps: Option<u32>,
+    },
+    /// Rollback applied migrations
+    Down {
+        /// Number of applied migrations to rollback
+        #[arg(short, long, default_value_t = 1)]
+        steps: u32,
+    },
+    /// Show the status of all migrations
+    Status,
+    /// Drop all tables and reapply all migrations (refused in prod)
+    Fresh {
+        /// Confirm dropping all tables
+        #[arg(long)]
+        yes: bool,
+    },
+}
+
+pub async fn run(db: &DatabaseConnection, command: MigrateCommand) -> anyhow::Result<()> {
+    match command {
+        MigrateCommand::Up { steps } => Migrator::up(db, steps).await?,
+        MigrateCommand::Down { steps } => Migrator::down(db, Some(steps)).await?,
+        MigrateCommand::Status => Migrator::status(db).await?,
+        MigrateCommand::Fresh { yes } => {
+            anyhow::ensure!(
+                !crate::config::get().is_prod(),
+                "migrate fresh is not allowed in prod"
+            );
+            anyhow::ensure!(yes, "migrate fresh drops all tables, pass --yes to confirm");
+            Migrator::fresh(db).await?
+        }
+    }
+    Ok(())
+}
@@ -10,3 +10,8 @@ pub fn verify_password<T: AsRef<str>>(password: T, hash: &str) -> ApiResult<bool
     let ret = bcrypt::verify(password.as_ref(), hash)?;
     Ok(ret)
 }
+
+/// 比较耗时与内容无关，避免通过响应时间猜测 token
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
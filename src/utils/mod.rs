@@ -1,2 +1,3 @@
 pub mod crypt;
 pub mod id;
+pub mod sheet;
@@ -0,0 +1,112 @@
+use std::io::Cursor;
+
+use anyhow::Context;
+use calamine::{Data, Reader};
+use csv::StringRecord;
+use rust_xlsxwriter::Workbook;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SheetFormat {
+    #[default]
+    Csv,
+    Xlsx,
+}
+
+impl SheetFormat {
+    pub fn from_file_name(file_name: &str) -> Option<Self> {
+        let (_, extension) = file_name.rsplit_once('.')?;
+        match extension.to_ascii_lowercase().as_str() {
+            "csv" => Some(SheetFormat::Csv),
+            "xlsx" | "xls" => Some(SheetFormat::Xlsx),
+            _ => None,
+        }
+    }
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            SheetFormat::Csv => "text/csv; charset=utf-8",
+            SheetFormat::Xlsx => {
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+            }
+        }
+    }
+    pub fn extension(&self) -> &'static str {
+        match self {
+            SheetFormat::Csv => "csv",
+            SheetFormat::Xlsx => "xlsx",
+        }
+    }
+}
+
+/// 读取表格文件，返回表头和数据行（xlsx 只读取第一个工作表）
+pub fn read_records(
+    format: SheetFormat,
+    bytes: &[u8],
+) -> anyhow::Result<(StringRecord, Vec<StringRecord>)> {
+    match format {
+        SheetFormat::Csv => {
+            let mut reader = csv::ReaderBuilder::new()
+                .trim(csv::Trim::All)
+                .from_reader(bytes);
+            let headers = reader
+                .headers()
+                .context("can not read csv headers")?
+                .clone();
+            let rows = reader
+                .records()
+                .collect::<Result<Vec<_>, _>>()
+                .context("can not read csv rows")?;
+            Ok((headers, rows))
+        }
+        SheetFormat::Xlsx => {
+            let mut workbook = calamine::open_workbook_auto_from_rs(Cursor::new(bytes))
+                .context("can not open workbook")?;
+            let range = workbook
+                .worksheet_range_at(0)
+                .ok_or_else(|| anyhow::anyhow!("workbook has no worksheet"))?
+                .context("can not read worksheet")?;
+            let mut rows = range
+                .rows()
+                .map(|row| row.iter().map(cell_to_string).collect::<StringRecord>());
+            let headers = rows.next().unwrap_or_default();
+            Ok((headers, rows.collect()))
+        }
+    }
+}
+
+fn cell_to_string(cell: &Data) -> String {
+    match cell {
+        Data::DateTime(datetime) => datetime
+            .as_datetime()
+            .map(|datetime| datetime.date().to_string())
+            .unwrap_or_else(|| cell.to_string()),
+        Data::Empty => String::new(),
+        _ => cell.to_string().trim().to_string(),
+    }
+}
+
+/// 生成 xlsx 文件内容（首行为表头）
+pub fn write_xlsx(headers: &[&str], rows: &[Vec<String>]) -> anyhow::Result<Vec<u8>> {
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    worksheet.write_row(0, 0, headers.iter().copied())?;
+    for (index, row) in rows.iter().enumerate() {
+        worksheet.write_row(index as u32 + 1, 0, row.iter().map(String::as_str))?;
+    }
+    Ok(workbook.save_to_buffer()?)
+}
+
+/// 生成 csv 文本（用于分批输出）
+pub fn write_csv<I, T>(rows: I) -> anyhow::Result<Vec<u8>>
+where
+    I: IntoIterator<Item = T>,
+    T: IntoIterator,
+    T::Item: AsRef<[u8]>,
+{
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for row in rows {
+        writer.write_record(row)?;
+    }
+    Ok(writer.into_inner()?)
+}